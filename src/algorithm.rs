@@ -0,0 +1,273 @@
+// Algorithms
+//
+// An algorithm is a move sequence in the notation of `notation.rs`, extended by
+// the usual shorthands for writing algorithms by hand:
+//
+//     [A: B]    conjugate, A B A'
+//     [A, B]    commutator, A B A' B'
+//     (A)n      A repeated n times
+//
+// which can be nested, e.g. `[R: [U, F]] (R U R' U')2`. The shorthands are
+// expanded while parsing, so an `Algorithm` is a plain list of turns.
+
+use crate::group::PocketCube;
+use crate::notation::{Amount, MoveSequence, ParseError, Turn};
+use crate::types::PermId;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Algorithm {
+    turns: Vec<Turn>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    // index into `chars`
+    pos: usize,
+}
+
+impl Algorithm {
+    pub fn new(turns: Vec<Turn>) -> Self {
+        Self { turns }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let algorithm = parser.parse_sequence()?;
+        match parser.peek() {
+            None => Ok(algorithm),
+            Some(':') | Some(',') => Err(parser.error("Separator outside of brackets")),
+            Some(_) => Err(parser.error("Unmatched bracket")),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    pub fn concat(&self, other: &Algorithm) -> Algorithm {
+        Algorithm::new([&self.turns[..], &other.turns[..]].concat())
+    }
+
+    pub fn inverse(&self) -> Algorithm {
+        Algorithm::new(self.turns.iter().rev().map(|x| x.inverse()).collect())
+    }
+
+    pub fn repeat(&self, count: usize) -> Algorithm {
+        Algorithm::new(self.turns.repeat(count))
+    }
+
+    // Mirrored across the plane between R and L
+    pub fn mirror(&self) -> Algorithm {
+        Algorithm::new(self.turns.iter().map(|x| x.mirror()).collect())
+    }
+
+    // A B A'
+    pub fn conjugate(a: &Algorithm, b: &Algorithm) -> Algorithm {
+        a.concat(b).concat(&a.inverse())
+    }
+
+    // A B A' B'
+    pub fn commutator(a: &Algorithm, b: &Algorithm) -> Algorithm {
+        a.concat(b).concat(&a.inverse()).concat(&b.inverse())
+    }
+
+    // Merges adjacent turns of the same face or around the same axis, dropping
+    // the ones that cancel, until no two adjacent turns can be merged
+    pub fn simplify(&self) -> Algorithm {
+        let mut turns: Vec<Turn> = Vec::new();
+        for turn in &self.turns {
+            match turns.last() {
+                Some(last)
+                    if last.with_amount(Amount::Clockwise)
+                        == turn.with_amount(Amount::Clockwise) =>
+                {
+                    let quarter_turns =
+                        last.get_amount().quarter_turns() + turn.get_amount().quarter_turns();
+                    turns.pop();
+                    if let Some(amount) = Amount::from_quarter_turns(quarter_turns) {
+                        turns.push(turn.with_amount(amount));
+                    }
+                }
+                _ => turns.push(*turn),
+            }
+        }
+        Algorithm::new(turns)
+    }
+
+    pub fn to_sequence(&self) -> MoveSequence {
+        MoveSequence::new(self.turns.clone())
+    }
+
+    pub fn apply(&self, pc: &mut PocketCube) {
+        self.to_sequence().apply(pc);
+    }
+
+    // State of the solved cube after the algorithm
+    pub fn get_perm_id(&self) -> PermId {
+        let mut pc = PocketCube::new();
+        self.apply(&mut pc);
+        pc.get_perm_id()
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    // Error at the current character
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            column: self.pos + 1,
+            token: self.peek().map_or(String::new(), |c| c.to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    // Turns and shorthands up to the end of the text or a closing bracket or
+    // separator, which is not consumed
+    fn parse_sequence(&mut self) -> Result<Algorithm, ParseError> {
+        let mut algorithm = Algorithm::new(Vec::new());
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                None | Some(']') | Some(')') | Some(':') | Some(',') => return Ok(algorithm),
+                Some('[') => self.parse_bracket()?,
+                Some('(') => self.parse_group()?,
+                Some(_) => self.parse_turn()?,
+            };
+            algorithm = algorithm.concat(&part);
+        }
+    }
+
+    fn parse_bracket(&mut self) -> Result<Algorithm, ParseError> {
+        let open = self.error("Unclosed bracket");
+        self.pos += 1;
+        let a = self.parse_sequence()?;
+        let separator = self.peek();
+        if separator != Some(':') && separator != Some(',') {
+            return Err(match separator {
+                None => open,
+                Some(_) => self.error("Expected ':' or ','"),
+            });
+        }
+        self.pos += 1;
+        let b = self.parse_sequence()?;
+        match self.peek() {
+            Some(']') => self.pos += 1,
+            None => return Err(open),
+            Some(_) => return Err(self.error("Expected ']'")),
+        }
+        Ok(if separator == Some(':') {
+            Algorithm::conjugate(&a, &b)
+        } else {
+            Algorithm::commutator(&a, &b)
+        })
+    }
+
+    fn parse_group(&mut self) -> Result<Algorithm, ParseError> {
+        let open = self.error("Unclosed parenthesis");
+        self.pos += 1;
+        let a = self.parse_sequence()?;
+        match self.peek() {
+            Some(')') => self.pos += 1,
+            None => return Err(open),
+            Some(_) => return Err(self.error("Expected ')'")),
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        let count = if digits.is_empty() {
+            1
+        } else {
+            digits.parse().map_err(|_| ParseError {
+                column: start + 1,
+                token: digits.clone(),
+                message: "Invalid repetition count".to_string(),
+            })?
+        };
+        Ok(a.repeat(count))
+    }
+
+    fn parse_turn(&mut self) -> Result<Algorithm, ParseError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !"[](),:".contains(c))
+        {
+            self.pos += 1;
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+        Ok(Algorithm::new(vec![Turn::parse(&token, start + 1)?]))
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_sequence())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alg(text: &str) -> Algorithm {
+        Algorithm::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_shorthands() {
+        assert_eq!(alg("[R: U]").to_string(), "R U R'");
+        assert_eq!(alg("[R, U2]").to_string(), "R U2 R' U2");
+        assert_eq!(alg("(R U)3 F").to_string(), "R U R U R U F");
+        assert_eq!(alg("[F: [R, U]]"), alg("F R U R' U' F'"));
+        assert_eq!(alg("[x L: (R)2]").to_string(), "x L R R L' x'");
+        assert_eq!(Algorithm::parse("[R U").unwrap_err().column, 1);
+        assert_eq!(Algorithm::parse("R U] F").unwrap_err().column, 4);
+        assert_eq!(Algorithm::parse("[R U F]").unwrap_err().token, "]");
+        assert_eq!(Algorithm::parse("(R Q)").unwrap_err().token, "Q");
+    }
+
+    #[test]
+    fn test_operations() {
+        let a = alg("R U' F2 x");
+        assert_eq!(a.inverse().to_string(), "x' F2 U R'");
+        assert_eq!(a.mirror().to_string(), "L' U F2 x");
+        assert_eq!(a.concat(&a.inverse()).get_perm_id().get_id(), 0);
+        assert_eq!(a.repeat(2), a.concat(&a));
+        assert_eq!(alg("(R U R' U')6").get_perm_id().get_id(), 0);
+        // mirroring maps the solved cube and inverses to themselves
+        let sexy = alg("[R, U]");
+        assert_eq!(sexy.mirror().inverse(), sexy.inverse().mirror());
+        assert_eq!(sexy.mirror().repeat(6).get_perm_id().get_id(), 0);
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(alg("R R U U' R").simplify().to_string(), "R'");
+        assert_eq!(alg("R U U' R'").simplify().len(), 0);
+        assert_eq!(alg("F2 F' x x R L").simplify().to_string(), "F x2 R L");
+        let a = alg("[R U: F] (U2)3 [R, F]");
+        assert_eq!(
+            a.simplify().get_perm_id().get_id(),
+            a.get_perm_id().get_id()
+        );
+    }
+}
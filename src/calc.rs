@@ -1,11 +1,19 @@
+use crate::error::CubeError;
+use crate::schedule::{Schedule, ScheduleEntry};
+use crate::table::{open_table, Checksum, Endianness, TableError, TableHeader, TABLE_HEADER_SIZE};
+use crate::types::PermId;
 use crate::{group::PocketCube, types::PERMID_COUNT};
+use memmap2::Mmap;
 use std::cmp::min;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::Write;
 
-type MiniTableRow = [u32; 4];
-type MiniTable = Vec<MiniTableRow>;
+pub type MiniTableRow = [u32; 4];
+pub type MiniTable = Vec<MiniTableRow>;
+
+// Moves of the MiniTable columns, as recorded in the table file header
+pub const MINI_TABLE_MOVES: &str = "R U F";
+pub const MINI_TABLE_ROW_WIDTH: u32 = 16;
 
 pub fn generate_mini_table() -> MiniTable {
     let mut table: MiniTable = vec![[0, 0, 0, 1]; PERMID_COUNT];
@@ -18,7 +26,7 @@ pub fn generate_mini_table() -> MiniTable {
         let start = x * BATCH_SIZE;
         let end = min(PERMID_COUNT, (x + 1) * BATCH_SIZE);
         for i in start..end {
-            pc.apply_id((i as u32).into());
+            pc.apply_id(PermId::from_id(i as u32));
             let branches = pc.get_branches();
             for j in 0..3 {
                 table[i][j] = branches[j].get_id();
@@ -40,38 +48,54 @@ pub fn generate_mini_table() -> MiniTable {
 //     }
 // }
 
-pub fn write_to_file(data: &MiniTable, fname: &str) {
-    let mut f = File::create(fname).expect("Unable to create file");
+pub fn write_to_file(data: &MiniTable, fname: &str) -> Result<(), TableError> {
+    let mut checksum = Checksum::new();
+    for i in data {
+        checksum.update(&convert(i));
+    }
+    let header = TableHeader::new(
+        MINI_TABLE_MOVES,
+        MINI_TABLE_ROW_WIDTH,
+        data.len() as u32,
+        checksum.get(),
+    );
+
+    let mut f = File::create(fname)?;
+    header.write(&mut f)?;
     for i in data {
-        f.write_all(&convert(i)).expect("Unable to write data");
+        f.write_all(&convert(i))?;
     }
+    Ok(())
 }
 
-pub fn load_mini_table(fname: &str) -> MiniTable {
-    let mut f = File::open(fname).expect("No table file found");
-    // let mut buffer = vec![[0, 0, 0, 0] as MiniTableRow; PERMID_COUNT];
-    let mut table: MiniTable = Vec::new();
-    let mut buffer = vec![0 as u8; PERMID_COUNT * 4 * 4];
-    f.read(&mut buffer)
-        .expect("Buffer overflow while reading table file");
+// Reads the rows following an already read header, which is validated first
+pub fn read_mini_table(
+    reader: &mut impl Read,
+    header: &TableHeader,
+) -> Result<MiniTable, TableError> {
+    header.validate(MINI_TABLE_MOVES, MINI_TABLE_ROW_WIDTH, PERMID_COUNT as u32)?;
+    let mut table: MiniTable = Vec::with_capacity(PERMID_COUNT);
+    let mut checksum = Checksum::new();
+    let mut buffer = [0; MINI_TABLE_ROW_WIDTH as usize];
     for _ in 0..PERMID_COUNT {
-        let num1 = pop_u32(&mut buffer);
-        let num2 = pop_u32(&mut buffer);
-        let num3 = pop_u32(&mut buffer);
-        let num4 = pop_u32(&mut buffer);
-        table.push([num4, num3, num2, num1]);
+        reader.read_exact(&mut buffer)?;
+        checksum.update(&buffer);
+        table.push(decode_row(&buffer, header.endianness));
     }
-
-    table.reverse();
-    table
+    header.validate_checksum(&checksum)?;
+    Ok(table)
 }
 
-fn pop_u32(arr: &mut Vec<u8>) -> u32 {
-    let big = (arr.pop().unwrap() as u32)
-        + ((arr.pop().unwrap() as u32) << 8)
-        + ((arr.pop().unwrap() as u32) << 16)
-        + ((arr.pop().unwrap() as u32) << 24);
-    u32::to_be(big)
+pub fn decode_row(bytes: &[u8; 16], endianness: Endianness) -> MiniTableRow {
+    let mut row = [0; 4];
+    for i in 0..4 {
+        let num: [u8; 4] = bytes[4 * i..][..4].try_into().unwrap();
+        row[i] = match endianness {
+            Endianness::Little => u32::from_le_bytes(num),
+            Endianness::Big => u32::from_be_bytes(num),
+        };
+    }
+    row
 }
 
 // From: https://stackoverflow.com/a/72631195
@@ -83,51 +107,246 @@ fn convert(data: &[u32; 4]) -> [u8; 16] {
     res
 }
 
+// Anything that can answer the MiniTable's R, U and F targets of a state
+pub trait BranchTable {
+    fn get_branches(&self, id: u32) -> [u32; 3];
+}
+
+impl BranchTable for MiniTable {
+    fn get_branches(&self, id: u32) -> [u32; 3] {
+        let row = &self[id as usize];
+        [row[0], row[1], row[2]]
+    }
+}
+
+// MiniTable file mapped into memory. Rows are decoded on access straight from
+// the mapping, so opening is instant and the pages are shared between processes.
+pub struct MappedMiniTable {
+    mmap: Mmap,
+    endianness: Endianness,
+}
+
+impl MappedMiniTable {
+    // Only the header and the file size are validated, see `verify_checksum`
+    pub fn open(fname: &str) -> Result<Self, TableError> {
+        let (reader, header) = open_table(
+            fname,
+            MINI_TABLE_MOVES,
+            MINI_TABLE_ROW_WIDTH,
+            PERMID_COUNT as u32,
+        )?;
+        // Safety: the table file must not be modified while it is mapped
+        let mmap = unsafe { Mmap::map(reader.get_ref())? };
+        Ok(Self {
+            mmap,
+            endianness: header.endianness,
+        })
+    }
+
+    fn get_row_bytes(&self, id: u32) -> &[u8; 16] {
+        let start = TABLE_HEADER_SIZE + id as usize * MINI_TABLE_ROW_WIDTH as usize;
+        self.mmap[start..start + MINI_TABLE_ROW_WIDTH as usize]
+            .try_into()
+            .unwrap()
+    }
+
+    pub fn get_row(&self, id: u32) -> MiniTableRow {
+        decode_row(self.get_row_bytes(id), self.endianness)
+    }
+
+    // Reads the whole mapping once, which defeats the instant startup
+    pub fn verify_checksum(&self) -> Result<(), TableError> {
+        let header_bytes: &[u8; TABLE_HEADER_SIZE] =
+            self.mmap[..TABLE_HEADER_SIZE].try_into().unwrap();
+        let header = TableHeader::from_bytes(header_bytes)?;
+        let mut checksum = Checksum::new();
+        checksum.update(&self.mmap[TABLE_HEADER_SIZE..]);
+        header.validate_checksum(&checksum)
+    }
+}
+
+impl BranchTable for MappedMiniTable {
+    fn get_branches(&self, id: u32) -> [u32; 3] {
+        let row = self.get_row(id);
+        [row[0], row[1], row[2]]
+    }
+}
+
 pub struct MtableIterator {
-    mtable: MiniTable,
+    mtable: Box<dyn BranchTable>,
     probs: Vec<f64>,
+    weights: [f64; 3],
+    // states that keep their probability unless the step disperses
+    absorbing: Vec<bool>,
     epoch: usize,
 }
 
 impl MtableIterator {
     pub fn new() -> Self {
+        Self::with_state_count(PERMID_COUNT)
+    }
+
+    // For a table over a subset of the states, e.g. a `Subgroup`, where state 0
+    // is still the solved state
+    pub fn with_state_count(state_count: usize) -> Self {
         Self {
-            mtable: Vec::new(),
-            probs: vec![0.0; PERMID_COUNT],
+            mtable: Box::new(MiniTable::new()),
+            probs: vec![0.0; state_count],
+            weights: [1.0 / 3.0; 3],
+            absorbing: (0..state_count).map(|x| x == 0).collect(),
             epoch: 0,
         }
     }
 
     pub fn load_mtable(&mut self, mtable: MiniTable) {
-        self.mtable = mtable;
+        self.mtable = Box::new(mtable);
+    }
+
+    pub fn load_table(&mut self, table: Box<dyn BranchTable>) {
+        self.mtable = table;
     }
 
     pub fn set_zero(&mut self) {
         self.probs[0] = 1.0;
     }
 
-    // if disperse is true,
-    pub fn iterate(&mut self, disperse: bool) {
-        let mut new_probs: Vec<f64> = vec![0.0; PERMID_COUNT];
-        if !disperse {
-            new_probs[0] = self.probs[0];
+    pub fn set_state(&mut self, id: u32) {
+        self.set_distribution(&[id]);
+    }
+
+    // Uniform over the given states
+    pub fn set_distribution(&mut self, ids: &[u32]) {
+        self.probs.iter_mut().for_each(|x| *x = 0.0);
+        for id in ids {
+            self.probs[*id as usize] += 1.0 / (ids.len() as f64);
+        }
+    }
+
+    pub fn set_uniform(&mut self) {
+        let prob = 1.0 / (self.probs.len() as f64);
+        self.probs.iter_mut().for_each(|x| *x = prob);
+    }
+
+    // Only the solved state is absorbing by default
+    pub fn set_absorbing(&mut self, ids: &[u32]) {
+        self.absorbing.iter_mut().for_each(|x| *x = false);
+        for id in ids {
+            self.absorbing[*id as usize] = true;
         }
-        for i in (if disperse { 0 } else { 1 })..PERMID_COUNT {
+    }
+
+    // Probabilities of the R, U and F moves, in the MiniTable column order
+    pub fn set_weights(&mut self, weights: [f64; 3]) {
+        self.weights = weights;
+    }
+
+    // Iterates with the weights the schedule specifies for the current epoch
+    pub fn iterate_schedule<'a>(
+        &mut self,
+        schedule: &'a Schedule,
+        disperse: bool,
+    ) -> Option<&'a ScheduleEntry> {
+        let entry = schedule.get_entry(self.epoch)?;
+        self.set_weights(entry.get_weights());
+        self.iterate(disperse);
+        Some(entry)
+    }
+
+    // if disperse is true, the absorbing states are left as well
+    pub fn iterate(&mut self, disperse: bool) {
+        let mut new_probs: Vec<f64> = vec![0.0; self.probs.len()];
+        for i in 0..self.probs.len() {
             let prob = &self.probs[i];
-            let row = &self.mtable[i];
-            let prob_each = prob / 3.0;
+            if !disperse && self.absorbing[i] {
+                new_probs[i] += prob;
+                continue;
+            }
+            let row = self.mtable.get_branches(i as u32);
             for j in 0..3 {
-                new_probs[row[j] as usize] += prob_each;
+                new_probs[row[j] as usize] += prob * self.weights[j];
             }
         }
         self.probs = new_probs;
         self.epoch += 1;
     }
 
-    pub fn get_prob(&self, id: u32) -> f64 {
-        if id >= (PERMID_COUNT as u32) {
-            panic!("Invalid id to get_prob")
+    pub fn get_prob(&self, id: u32) -> Result<f64, CubeError> {
+        self.probs
+            .get(id as usize)
+            .copied()
+            .ok_or(CubeError::PermId(id))
+    }
+
+    pub fn get_probs(&self) -> &[f64] {
+        &self.probs
+    }
+
+    // Returns the indexes of the k most probable states, sorted by descending
+    // probability. With the full table an index is the `PermId`, with a subgroup
+    // table it is mapped by `Subgroup::get_perm_id`.
+    pub fn top_k(&self, k: usize) -> Vec<(u32, f64)> {
+        let k = min(k, self.probs.len());
+        if k == 0 {
+            return Vec::new();
         }
-        return self.probs[id as usize];
+        let mut ids: Vec<u32> = (0..self.probs.len() as u32).collect();
+        let by_prob_desc = |a: &u32, b: &u32| {
+            self.probs[*b as usize]
+                .total_cmp(&self.probs[*a as usize])
+                .then(a.cmp(b))
+        };
+        ids.select_nth_unstable_by(k - 1, by_prob_desc);
+        ids.truncate(k);
+        ids.sort_unstable_by(by_prob_desc);
+        ids.iter()
+            .map(|id| (*id, self.probs[*id as usize]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packed::AnyMiniTable;
+
+    #[test]
+    fn test_load_missing_table() {
+        let result = AnyMiniTable::load("out/does_not_exist.tbl");
+        assert!(matches!(result, Err(TableError::Io(_))));
+    }
+
+    #[test]
+    fn test_load_truncated_table() {
+        // unique per process, so that concurrent test runs do not share it
+        let fname =
+            std::env::temp_dir().join(format!("pocket_cube_truncated_{}.tbl", std::process::id()));
+        let header = TableHeader::new(
+            MINI_TABLE_MOVES,
+            MINI_TABLE_ROW_WIDTH,
+            PERMID_COUNT as u32,
+            0,
+        );
+        let mut f = File::create(&fname).unwrap();
+        header.write(&mut f).unwrap();
+        f.write_all(&convert(&[1, 2, 3, 0])).unwrap();
+        drop(f);
+
+        let result = AnyMiniTable::load(fname.to_str().unwrap());
+        assert!(matches!(
+            result,
+            Err(TableError::Truncated { found: 16, .. })
+        ));
+        std::fs::remove_file(fname).unwrap();
+    }
+
+    #[test]
+    fn test_top_k_initial() {
+        let mut iterator = MtableIterator::new();
+        iterator.set_zero();
+        let top = iterator.top_k(3);
+        assert_eq!(top.len(), 3);
+        assert_eq!(top[0].0, 0);
+        assert_eq!(top[0].1, 1.0);
+        assert_eq!(top[1].1, 0.0);
     }
 }
@@ -0,0 +1,132 @@
+// Conjugacy classes of the anchored pocket cube group
+//
+// The class of a state x is {g⁻¹ x g} over all states g. Since R, U and F
+// generate the group, it is also the orbit of x under conjugation by R, U and F
+// alone, which is found by a breadth-first search over the `PermId`s. Classes
+// are numbered in the order of their lowest `PermId`, which is used as the
+// representative.
+
+use crate::moves::BASE_MOVES;
+use crate::state::CubeState;
+use crate::types::{PermId, PERMID_COUNT};
+use std::collections::VecDeque;
+
+pub struct ConjugacyClass {
+    pub representative: CubeState,
+    pub size: usize,
+    // order of every element of the class
+    pub order: usize,
+}
+
+const UNASSIGNED: u32 = u32::MAX;
+
+// The classes of a group of `state_count` states in `PermId` order, where
+// `conjugate` conjugates a state by the i-th generator
+fn find_classes(
+    state_count: usize,
+    generator_count: usize,
+    conjugate: impl Fn(u32, usize) -> u32,
+) -> Vec<Vec<u32>> {
+    let mut class_ids = vec![UNASSIGNED; state_count];
+    let mut classes = Vec::new();
+    let mut queue = VecDeque::new();
+    for start in 0..state_count as u32 {
+        if class_ids[start as usize] != UNASSIGNED {
+            continue;
+        }
+        let class_id = classes.len() as u32;
+        let mut members = vec![start];
+        class_ids[start as usize] = class_id;
+        queue.push_back(start);
+        while let Some(id) = queue.pop_front() {
+            for i in 0..generator_count {
+                let conjugate = conjugate(id, i);
+                if class_ids[conjugate as usize] == UNASSIGNED {
+                    class_ids[conjugate as usize] = class_id;
+                    members.push(conjugate);
+                    queue.push_back(conjugate);
+                }
+            }
+        }
+        classes.push(members);
+    }
+    classes
+}
+
+pub fn conjugacy_classes() -> Vec<ConjugacyClass> {
+    let generators: Vec<(CubeState, CubeState)> = BASE_MOVES
+        .iter()
+        .map(|mv| (CubeState::from(*mv), CubeState::from(mv.inverse())))
+        .collect();
+    let conjugate = |id: u32, i: usize| {
+        let state = CubeState::from(PermId::from_id(id));
+        let (g, g_inverse) = generators[i];
+        (g_inverse * state * g).get_perm_id().get_id()
+    };
+    find_classes(PERMID_COUNT, generators.len(), conjugate)
+        .iter()
+        .map(|members| {
+            let representative = CubeState::from(PermId::from_id(members[0]));
+            ConjugacyClass {
+                representative,
+                size: members.len(),
+                order: representative.order(),
+            }
+        })
+        .collect()
+}
+
+// Number of states and of classes of every element order, by ascending order
+pub fn order_distribution(classes: &[ConjugacyClass]) -> Vec<(usize, usize, usize)> {
+    let mut distribution: Vec<(usize, usize, usize)> = Vec::new();
+    for class in classes {
+        match distribution.iter_mut().find(|x| x.0 == class.order) {
+            Some(entry) => {
+                entry.1 += class.size;
+                entry.2 += 1;
+            }
+            None => distribution.push((class.order, class.size, 1)),
+        }
+    }
+    distribution.sort_unstable();
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+
+    #[test]
+    fn test_find_classes_s3() {
+        // S3 as permutations of 3 elements, generated by two transpositions
+        let perms: [[usize; 3]; 6] = [
+            [0, 1, 2],
+            [1, 0, 2],
+            [0, 2, 1],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        let index = |p: [usize; 3]| perms.iter().position(|x| *x == p).unwrap() as u32;
+        let conjugate = |id: u32, i: usize| {
+            // generators are their own inverses
+            let (g, x) = (perms[i + 1], perms[id as usize]);
+            index([g[x[g[0]]], g[x[g[1]]], g[x[g[2]]]])
+        };
+        let classes = find_classes(6, 2, conjugate);
+        let sizes: Vec<usize> = classes.iter().map(|x| x.len()).collect();
+        assert_eq!(sizes, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_order_distribution() {
+        let class = |mv: Move, size| ConjugacyClass {
+            representative: CubeState::from(mv),
+            size,
+            order: CubeState::from(mv).order(),
+        };
+        let classes = [class(Move::R, 5), class(Move::U2, 2), class(Move::F, 1)];
+        assert_eq!(order_distribution(&classes), vec![(2, 2, 1), (4, 6, 2)]);
+    }
+}
@@ -0,0 +1,280 @@
+// Command-line interface
+//
+// Without a subcommand the GUI is launched. Every command that reads the
+// MiniTable takes `--table`, and every command that writes results takes
+// `--output` and `--iterations`.
+
+use crate::facelets::FaceletOrder;
+use crate::notation::MoveSequence;
+use crate::packed::TableFormat;
+use crate::schedule::Schedule;
+use crate::types::PERMID_COUNT;
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+
+pub const DEFAULT_TABLE: &str = "out/output.tbl";
+
+#[derive(Parser)]
+#[command(version, about = "Pocket cube probability calculator")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Generate the MiniTable
+    Gen {
+        /// Table file to write
+        #[arg(long, default_value = DEFAULT_TABLE)]
+        table: String,
+    },
+    /// Probability of the solved state after every step of a random walk
+    Calc(CalcArgs),
+    /// Same as `calc --disperse`
+    Calcdis(CalcArgs),
+    /// Convert a MiniTable file between the padded and the packed format
+    Convert {
+        input: String,
+        output: String,
+        #[arg(value_enum)]
+        format: TableFormat,
+    },
+    /// Write the MiniTable in a format for other tools
+    Export {
+        #[arg(value_enum)]
+        format: ExportFormat,
+        output: String,
+        #[arg(long, default_value = DEFAULT_TABLE)]
+        table: String,
+    },
+    /// Check the invariants of a MiniTable file
+    Verify {
+        #[arg(default_value = DEFAULT_TABLE)]
+        table: String,
+        /// Compare every state against the cube model instead of a sample
+        #[arg(long)]
+        all: bool,
+    },
+    /// Position and twist marginal chains
+    Marginal {
+        /// Also run the full chain and compare it with the product of the marginals
+        #[arg(long)]
+        compare: bool,
+        #[arg(long, default_value = DEFAULT_TABLE)]
+        table: String,
+        #[arg(long, default_value = "out/marginal.csv")]
+        output: String,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+    },
+    /// Position and twist distribution of every single cubic
+    Cubic {
+        #[arg(long, default_value = "out/cubic.csv")]
+        output: String,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+    },
+    /// Noisy-optimal solver model
+    Solver {
+        /// Optimal move probabilities to model, e.g. `0.5,0.9`
+        #[arg(long, value_delimiter = ',')]
+        p: Vec<f64>,
+        /// Start from this state instead of a uniformly random one
+        #[arg(long, value_parser = parse_state)]
+        start: Option<u32>,
+        #[arg(long, default_value = DEFAULT_TABLE)]
+        table: String,
+        #[arg(long, default_value = "out/solver.csv")]
+        output: String,
+        #[arg(long, default_value = "out/solver_summary.csv")]
+        summary: String,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+    },
+    /// Distribution of the optimal distance after every walk length
+    Scramble {
+        #[arg(long, default_value = DEFAULT_TABLE)]
+        table: String,
+        #[arg(long, default_value = "out/scramble.csv")]
+        output: String,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+    },
+    /// Apply a move sequence like `R U' F2 L` to the solved cube
+    State {
+        #[arg(required = true, allow_hyphen_values = true)]
+        moves: Vec<String>,
+    },
+    /// Expand and simplify an algorithm like `[R U: F] (R U R' U')2`
+    Alg {
+        algorithm: String,
+        /// Mirror the algorithm across the plane between R and L
+        #[arg(long)]
+        mirror: bool,
+        /// Invert the algorithm
+        #[arg(long)]
+        inverse: bool,
+    },
+    /// The state that takes the state of one algorithm to that of another
+    Relative {
+        /// Algorithm reaching the starting state, e.g. `R U`
+        from: String,
+        /// Algorithm reaching the target state
+        to: String,
+    },
+    /// Cycle notation, cycle type, parity and order of a state
+    #[command(group(ArgGroup::new("state").required(true)))]
+    Cycles {
+        /// Algorithm reaching the state, e.g. `R U F'`
+        #[arg(long, group = "state")]
+        moves: Option<String>,
+        /// Facelet string of the state, see the `facelets` command
+        #[arg(long, group = "state")]
+        facelets: Option<String>,
+        /// Order of the faces in `--facelets`
+        #[arg(long, value_enum, default_value = "implementation")]
+        order: FaceletOrder,
+        /// `PermId` of the state
+        #[arg(long, group = "state", value_parser = parse_state)]
+        id: Option<u32>,
+    },
+    /// Conjugacy classes of the group with their sizes and element orders
+    Classes {
+        /// CSV of the classes, the order distribution is written to `<stem>_orders.csv`
+        #[arg(long, default_value = "out/classes.csv")]
+        output: String,
+    },
+    /// Order, base and strong generating set of the group generated by algorithms
+    /// or any permutations of the 24 facelets, which may include L, D, B and
+    /// rotations
+    SchreierSims {
+        /// Generators as algorithms like `R`, `L2` or `"R U R' U'"`, or as cycles
+        /// of facelet indexes like `"(0 1 3 2) (4 16 12 8)"`
+        #[arg(required = true)]
+        generators: Vec<String>,
+        /// Test whether this algorithm or permutation is in the group and write
+        /// it as a product of the generators
+        #[arg(long)]
+        member: Option<String>,
+    },
+    /// Order and depth distribution of the subgroup generated by moves or states
+    Subgroup {
+        /// Generators as algorithms like `R`, `F2` or `"R U R' U'"`, or as `PermId`s
+        #[arg(required = true)]
+        generators: Vec<String>,
+        /// CSV of the number of states at every depth
+        #[arg(long, default_value = "out/subgroup.csv")]
+        output: String,
+        /// Also walk the subgroup for this many steps with every generator equally
+        /// likely, written to `<stem>_walk.csv` (at most 3 generators)
+        #[arg(long)]
+        walk: Option<usize>,
+    },
+    /// Read a state from 24 face letters, e.g. `UUUULLLLFFFFRRRRBBBBDDDD`
+    Facelets {
+        facelets: String,
+        /// Order of the faces, `urfdlb` is the order of most other cube programs
+        #[arg(long, value_enum, default_value = "implementation")]
+        order: FaceletOrder,
+    },
+    /// Launch the GUI
+    Gui,
+}
+
+#[derive(Args)]
+pub struct CalcArgs {
+    /// Experiment config file, see `config.rs`
+    #[arg(
+        long,
+        conflicts_with_all = ["output", "iterations", "disperse", "moves", "schedule", "start", "scramble"]
+    )]
+    pub config: Option<String>,
+    /// Table file, overrides the `table` of a config [default: out/output.tbl]
+    #[arg(long)]
+    pub table: Option<String>,
+    /// CSV file to write, `--top` and `--npy` files are written next to it
+    #[arg(long, default_value = "out/output.csv")]
+    pub output: String,
+    #[arg(long, default_value_t = 100)]
+    pub iterations: usize,
+    /// Let the walk leave the solved state again
+    #[arg(long)]
+    pub disperse: bool,
+    /// Moves of the walk with optional weights, e.g. `R,U` or `R,U,F:2`
+    #[arg(long, value_parser = parse_moves, conflicts_with = "schedule")]
+    pub moves: Option<String>,
+    /// Schedule file changing the move weights over time
+    #[arg(long)]
+    pub schedule: Option<String>,
+    /// Starting state as a `PermId`
+    #[arg(long, default_value_t = 0, value_parser = parse_state)]
+    pub start: u32,
+    /// Starting state as a move sequence, e.g. `R U' F2`
+    #[arg(long, value_parser = parse_scramble, conflicts_with = "start")]
+    pub scramble: Option<MoveSequence>,
+    /// Also write the k most probable states of every step
+    #[arg(long)]
+    pub top: Option<usize>,
+    /// Steps after which to save the distribution as `.npy`
+    #[arg(long, value_delimiter = ',')]
+    pub npy: Vec<usize>,
+    /// Compute the rows from small move tables instead of the table file
+    #[arg(long, conflicts_with_all = ["mmap", "format"])]
+    pub factored: bool,
+    /// Read the rows straight from the mapped table file
+    #[arg(long, conflicts_with = "format")]
+    pub mmap: bool,
+    /// Validate the checksum of the mapped table file
+    #[arg(long, requires = "mmap")]
+    pub checksum: bool,
+    /// In-memory table format, `auto` benchmarks both
+    #[arg(long, value_enum)]
+    pub format: Option<FormatArg>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FormatArg {
+    Padded,
+    Packed,
+    Auto,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Transition matrix of the uniform walk as Matrix Market
+    Mtx,
+    /// Move targets as a NumPy array
+    Npy,
+    /// Edge list CSV
+    Edges,
+}
+
+impl FormatArg {
+    // `None` for `auto`
+    pub fn to_format(self) -> Option<TableFormat> {
+        match self {
+            FormatArg::Padded => Some(TableFormat::Padded),
+            FormatArg::Packed => Some(TableFormat::Packed),
+            FormatArg::Auto => None,
+        }
+    }
+}
+
+fn parse_state(text: &str) -> Result<u32, String> {
+    match text.parse::<u32>() {
+        Ok(id) if (id as usize) < PERMID_COUNT => Ok(id),
+        _ => Err(format!("expected a PermId below {}", PERMID_COUNT)),
+    }
+}
+
+// A move set is a schedule with a single open ended entry, returned in the
+// schedule syntax
+fn parse_moves(text: &str) -> Result<String, String> {
+    let moves = text.replace(',', " ");
+    Schedule::parse(&format!("0.. {}", moves)).map_err(|e| e.message)?;
+    Ok(moves)
+}
+
+fn parse_scramble(text: &str) -> Result<MoveSequence, String> {
+    MoveSequence::parse(text).map_err(|e| e.to_string())
+}
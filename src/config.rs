@@ -0,0 +1,280 @@
+// Experiment configuration files
+//
+// A TOML file describing one `calc` run. Every field is optional:
+//
+//     table = "out/output.tbl"         # `--table` takes precedence
+//     output = "out/exp1/output.csv"   # the config is copied next to it
+//     steps = 100
+//     moves = "R U F:2"                # or `schedule = "file"`
+//     initial = "solved"               # "uniform" or { states = [1, 2] }
+//     absorbing = [0]                  # [] lets the walk leave every state
+//     columns = ["loop", "value", "near"]
+//
+//     [[targets]]                      # adds a column with the probability
+//     name = "near"                    # of the matching states
+//     max_distance = 2
+//
+// A target matches the states fulfilling all of its conditions: `states`,
+// `position` (`PosId`), `twist` (`RotId`), `min_distance` and `max_distance`.
+// The first step always leaves the absorbing states, as in `calc`.
+
+use crate::calc::BranchTable;
+use crate::schedule::Schedule;
+use crate::search;
+use crate::types::{PermId, PERMID_COUNT};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+
+// Columns every run can have, besides the targets
+pub const BASE_COLUMNS: [&str; 4] = ["loop", "value", "disperse", "schedule"];
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub table: Option<String>,
+    pub output: Option<String>,
+    #[serde(default = "default_steps")]
+    pub steps: usize,
+    pub moves: Option<String>,
+    pub schedule: Option<String>,
+    #[serde(default)]
+    pub initial: Initial,
+    #[serde(default = "default_absorbing")]
+    pub absorbing: Vec<u32>,
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    pub columns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Initial {
+    #[default]
+    Solved,
+    Uniform,
+    // uniform over the given states
+    States(Vec<u32>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    pub name: String,
+    // sorted
+    pub states: Option<Vec<u32>>,
+    pub position: Option<u32>,
+    pub twist: Option<u32>,
+    pub min_distance: Option<u8>,
+    pub max_distance: Option<u8>,
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub message: String,
+}
+
+fn default_steps() -> usize {
+    100
+}
+
+fn default_absorbing() -> Vec<u32> {
+    vec![0]
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            table: None,
+            output: None,
+            steps: default_steps(),
+            moves: None,
+            schedule: None,
+            initial: Initial::default(),
+            absorbing: default_absorbing(),
+            targets: Vec::new(),
+            columns: None,
+        }
+    }
+}
+
+impl ExperimentConfig {
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut config: Self = toml::from_str(text).map_err(|e| ConfigError {
+            message: e.to_string(),
+        })?;
+        config.validate()?;
+        // `Target::matches` looks the states up with a binary search
+        for target in &mut config.targets {
+            if let Some(states) = target.states.as_mut() {
+                states.sort_unstable();
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn load(fname: &str) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(fname).map_err(|e| ConfigError {
+            message: format!("Unable to read config file '{}': {}", fname, e),
+        })?;
+        Self::parse(&text)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let err = |message: String| Err(ConfigError { message });
+        if self.moves.is_some() && self.schedule.is_some() {
+            return err("Only one of `moves` and `schedule` can be given".to_string());
+        }
+        let states = match &self.initial {
+            Initial::States(states) if states.is_empty() => {
+                return err("The initial distribution has no states".to_string())
+            }
+            Initial::States(states) => states.iter(),
+            _ => [].iter(),
+        };
+        let target_states = self.targets.iter().flat_map(|x| x.states.iter().flatten());
+        if let Some(id) = states
+            .chain(self.absorbing.iter())
+            .chain(target_states)
+            .find(|x| **x as usize >= PERMID_COUNT)
+        {
+            return err(format!("Invalid state {}", id));
+        }
+        for (i, target) in self.targets.iter().enumerate() {
+            if BASE_COLUMNS.contains(&target.name.as_str())
+                || self.targets[..i].iter().any(|x| x.name == target.name)
+            {
+                return err(format!("Duplicate column name '{}'", target.name));
+            }
+        }
+        for column in self.get_columns() {
+            if !BASE_COLUMNS.contains(&column) && !self.targets.iter().any(|x| x.name == column) {
+                return err(format!("Unknown column '{}'", column));
+            }
+        }
+        Ok(())
+    }
+
+    // The base columns followed by the targets unless `columns` picks some
+    pub fn get_columns(&self) -> Vec<&str> {
+        match &self.columns {
+            Some(columns) => columns.iter().map(|x| x.as_str()).collect(),
+            None => BASE_COLUMNS
+                .iter()
+                .copied()
+                .chain(self.targets.iter().map(|x| x.name.as_str()))
+                .collect(),
+        }
+    }
+
+    pub fn get_schedule(&self) -> Result<Schedule, ConfigError> {
+        let to_config_error = |e: crate::schedule::ScheduleError| ConfigError {
+            message: e.to_string(),
+        };
+        match (&self.moves, &self.schedule) {
+            (Some(moves), _) => Schedule::parse(&format!("0.. {}", moves)).map_err(to_config_error),
+            (None, Some(fname)) => Schedule::load(fname).map_err(to_config_error),
+            (None, None) => Ok(Schedule::uniform()),
+        }
+    }
+
+    // States matched by every target, in the order of `targets`
+    pub fn resolve_targets(&self, table: &dyn BranchTable) -> Vec<Vec<u32>> {
+        let distances = if self.targets.iter().any(|x| x.uses_distance()) {
+            println!("Computing distances...");
+            Some(search::compute_distances(table, PERMID_COUNT))
+        } else {
+            None
+        };
+        self.targets
+            .iter()
+            .map(|target| {
+                (0..PERMID_COUNT as u32)
+                    .filter(|id| target.matches(*id, distances.as_deref()))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Target {
+    fn uses_distance(&self) -> bool {
+        self.min_distance.is_some() || self.max_distance.is_some()
+    }
+
+    // `distances` must be given if the target uses them
+    pub fn matches(&self, id: u32, distances: Option<&[u8]>) -> bool {
+        let perm_id = PermId::from_id(id);
+        let distance = distances.map_or(0, |x| x[id as usize]);
+        self.states
+            .as_ref()
+            .is_none_or(|x| x.binary_search(&id).is_ok())
+            && self
+                .position
+                .is_none_or(|x| perm_id.get_pos_id().get_id() == x)
+            && self
+                .twist
+                .is_none_or(|x| perm_id.get_rot_id().get_id() == x)
+            && self.min_distance.is_none_or(|x| distance >= x)
+            && self.max_distance.is_none_or(|x| distance <= x)
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid config: {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = ExperimentConfig::parse(
+            "steps = 10\nmoves = \"R U:3\"\ninitial = { states = [1, 2] }\nabsorbing = []\n\
+             [[targets]]\nname = \"twisted\"\nposition = 0\n\
+             [[targets]]\nname = \"listed\"\nstates = [9, 3, 7]\n",
+        )
+        .unwrap();
+        assert_eq!(config.steps, 10);
+        assert!(matches!(config.initial, Initial::States(ref x) if x == &[1, 2]));
+        assert!(config.absorbing.is_empty());
+        assert_eq!(
+            config.get_columns(),
+            vec!["loop", "value", "disperse", "schedule", "twisted", "listed"]
+        );
+        assert_eq!(
+            config
+                .get_schedule()
+                .unwrap()
+                .get_entry(5)
+                .unwrap()
+                .get_weights(),
+            [0.25, 0.75, 0.0]
+        );
+        assert!(config.targets[0].matches(5040, None));
+        assert!(!config.targets[0].matches(5041, None));
+        assert!([3, 7, 9]
+            .iter()
+            .all(|x| config.targets[1].matches(*x, None)));
+        assert!(!config.targets[1].matches(8, None));
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = ExperimentConfig::parse("").unwrap();
+        assert_eq!(config.steps, 100);
+        assert!(matches!(config.initial, Initial::Solved));
+        assert_eq!(config.absorbing, vec![0]);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(ExperimentConfig::parse("stpes = 10").is_err());
+        assert!(ExperimentConfig::parse("columns = [\"near\"]").is_err());
+        assert!(ExperimentConfig::parse("absorbing = [3674160]").is_err());
+        assert!(ExperimentConfig::parse("moves = \"R\"\nschedule = \"a\"").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 // Types and structs for describing a 2x2x2 cube's state
 
+use crate::error::CubeError;
 use std::fmt;
 use three_d::{vec3, Vec3};
 
@@ -74,6 +75,12 @@ impl fmt::Display for CornerPos {
     }
 }
 
+impl fmt::Display for CubeRealSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum CornerTwist {
     Rot0, // 0 1 2 (no permutation = identity)
@@ -144,19 +151,30 @@ impl CornerTwist {
     }
 }
 
-impl From<u32> for CornerPos {
-    fn from(value: u32) -> Self {
-        match value {
-            0 => CornerPos::FUR,
-            1 => CornerPos::FUL,
-            2 => CornerPos::FDR,
-            3 => CornerPos::FDL,
-            4 => CornerPos::BUR,
-            5 => CornerPos::BUL,
-            6 => CornerPos::BDR,
-            7 => CornerPos::BDL,
-            _ => unimplemented!("Invalid order, cannot be converted to CornerPos"),
-        }
+// Every position, indexed by its `u32` value
+pub const CORNER_POSITIONS: [CornerPos; 8] = [
+    CornerPos::FUR,
+    CornerPos::FUL,
+    CornerPos::FDR,
+    CornerPos::FDL,
+    CornerPos::BUR,
+    CornerPos::BUL,
+    CornerPos::BDR,
+    CornerPos::BDL,
+];
+
+// Every twist, indexed by its `u32` value
+pub const CORNER_TWISTS: [CornerTwist; 3] =
+    [CornerTwist::Rot0, CornerTwist::Rot1, CornerTwist::Rot2];
+
+impl TryFrom<u32> for CornerPos {
+    type Error = CubeError;
+
+    fn try_from(value: u32) -> Result<Self, CubeError> {
+        CORNER_POSITIONS
+            .get(value as usize)
+            .copied()
+            .ok_or(CubeError::CornerPos(value))
     }
 }
 
@@ -185,13 +203,13 @@ impl From<CornerTwist> for u32 {
     }
 }
 
-impl From<u32> for CornerTwist {
-    fn from(value: u32) -> Self {
-        match value {
-            0 => CornerTwist::Rot0,
-            1 => CornerTwist::Rot1,
-            2 => CornerTwist::Rot2,
-            _ => unimplemented!("Invalid order, cannot be converted to CornerTwist"),
-        }
+impl TryFrom<u32> for CornerTwist {
+    type Error = CubeError;
+
+    fn try_from(value: u32) -> Result<Self, CubeError> {
+        CORNER_TWISTS
+            .get(value as usize)
+            .copied()
+            .ok_or(CubeError::CornerTwist(value))
     }
 }
@@ -0,0 +1,101 @@
+// Crate-wide error type
+//
+// The core types reject invalid values through `TryFrom` with the variants
+// below instead of panicking. The errors of the file formats convert into it,
+// so a caller handling untrusted input can use `?` with a single error type.
+
+use crate::config::ConfigError;
+use crate::enums::CornerPos;
+use crate::facelets::FaceletError;
+use crate::notation::ParseError;
+use crate::schedule::ScheduleError;
+use crate::schreier::PermError;
+use crate::table::TableError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CubeError {
+    CornerPos(u32),
+    CornerTwist(u32),
+    PosId(u32),
+    RotId(u32),
+    PermId(u32),
+    // no cubic of the `PocketCube` is at this position
+    MissingCubic(CornerPos),
+    // an unsolved state without a move towards the solved state
+    NoOptimalMove(u32),
+    Notation(ParseError),
+    Facelets(FaceletError),
+    Perm(PermError),
+    Config(ConfigError),
+    Schedule(ScheduleError),
+    Table(TableError),
+}
+
+impl From<ParseError> for CubeError {
+    fn from(e: ParseError) -> Self {
+        CubeError::Notation(e)
+    }
+}
+
+impl From<FaceletError> for CubeError {
+    fn from(e: FaceletError) -> Self {
+        CubeError::Facelets(e)
+    }
+}
+
+impl From<PermError> for CubeError {
+    fn from(e: PermError) -> Self {
+        CubeError::Perm(e)
+    }
+}
+
+impl From<ConfigError> for CubeError {
+    fn from(e: ConfigError) -> Self {
+        CubeError::Config(e)
+    }
+}
+
+impl From<ScheduleError> for CubeError {
+    fn from(e: ScheduleError) -> Self {
+        CubeError::Schedule(e)
+    }
+}
+
+impl From<TableError> for CubeError {
+    fn from(e: TableError) -> Self {
+        CubeError::Table(e)
+    }
+}
+
+impl fmt::Display for CubeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CubeError::CornerPos(value) => {
+                write!(f, "Invalid corner position {}, expected 0 to 7", value)
+            }
+            CubeError::CornerTwist(value) => {
+                write!(f, "Invalid corner twist {}, expected 0 to 2", value)
+            }
+            CubeError::PosId(value) => write!(f, "Invalid PosId {}, expected 0 to 5039", value),
+            CubeError::RotId(value) => write!(f, "Invalid RotId {}, expected 0 to 728", value),
+            CubeError::PermId(value) => {
+                write!(f, "Invalid PermId {}, expected 0 to 3674159", value)
+            }
+            CubeError::MissingCubic(pos) => write!(f, "No cubic is at position {}", pos),
+            CubeError::NoOptimalMove(id) => write!(
+                f,
+                "State {} has no move towards the solved state, the table is incomplete",
+                id
+            ),
+            CubeError::Notation(e) => write!(f, "{}", e),
+            CubeError::Facelets(e) => write!(f, "{}", e),
+            CubeError::Perm(e) => write!(f, "{}", e),
+            CubeError::Config(e) => write!(f, "{}", e),
+            CubeError::Schedule(e) => write!(f, "{}", e),
+            CubeError::Table(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CubeError {}
@@ -0,0 +1,159 @@
+// Exporters for other tools
+//
+// The state index is always `PermId::get_id`. Matrix Market indices are 1-based,
+// so there state `id` is row and column `id + 1`.
+
+use crate::calc::BranchTable;
+use crate::schedule::SCHEDULE_MOVES;
+use csv::Writer;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+// Transition matrix of the random walk as a sparse Matrix Market file, where
+// entry (i, j) is the probability of moving from state i to state j
+pub fn write_matrix_market(
+    table: &dyn BranchTable,
+    state_count: usize,
+    weights: [f64; 3],
+    fname: &str,
+) -> io::Result<()> {
+    let mut f = BufWriter::new(File::create(fname)?);
+    writeln!(f, "%%MatrixMarket matrix coordinate real general")?;
+    writeln!(f, "% Transition matrix of the pocket cube random walk")?;
+    writeln!(
+        f,
+        "% Row and column k is the state with PermId::get_id() == k - 1"
+    )?;
+    writeln!(f, "% Entry (i, j) is the probability of moving from i to j")?;
+    write!(f, "% Move weights:")?;
+    for (name, weight) in SCHEDULE_MOVES.iter().zip(weights.iter()) {
+        write!(f, " {}:{}", name, weight)?;
+    }
+    writeln!(f)?;
+
+    // the size line comes first, so the entries are counted in a separate pass
+    let entry_count: usize = (0..state_count as u32)
+        .map(|id| get_transitions(table, id, weights).len())
+        .sum();
+    writeln!(f, "{} {} {}", state_count, state_count, entry_count)?;
+    for id in 0..state_count as u32 {
+        for (target, prob) in get_transitions(table, id, weights) {
+            writeln!(f, "{} {} {}", id + 1, target + 1, prob)?;
+        }
+    }
+    f.flush()
+}
+
+// Targets of a state with their probabilities, merging duplicate targets and
+// dropping moves with weight 0
+fn get_transitions(table: &dyn BranchTable, id: u32, weights: [f64; 3]) -> Vec<(u32, f64)> {
+    let mut transitions: Vec<(u32, f64)> = Vec::with_capacity(3);
+    for (target, weight) in table.get_branches(id).iter().zip(weights.iter()) {
+        if *weight == 0.0 {
+            continue;
+        }
+        match transitions.iter_mut().find(|x| x.0 == *target) {
+            Some(x) => x.1 += weight,
+            None => transitions.push((*target, *weight)),
+        }
+    }
+    transitions
+}
+
+// One row per move: source, target and the move
+pub fn write_edge_list(table: &dyn BranchTable, state_count: usize, fname: &str) -> io::Result<()> {
+    let mut wtr = Writer::from_path(fname)?;
+    wtr.write_record(["source", "target", "move"])?;
+    for id in 0..state_count as u32 {
+        for (target, name) in table.get_branches(id).iter().zip(SCHEDULE_MOVES.iter()) {
+            wtr.write_record([&id.to_string(), &target.to_string(), *name])?;
+        }
+    }
+    wtr.flush()
+}
+
+// Header of a version 1.0 `.npy` file, padded so that the data is 64-byte aligned
+fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [x] => format!("({},)", x),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    // magic, version and length take 10 bytes, the dict ends with a newline
+    let padding = (64 - (NPY_MAGIC.len() + 4 + dict.len() + 1) % 64) % 64;
+    dict.push_str(&" ".repeat(padding));
+    dict.push('\n');
+
+    let mut header = NPY_MAGIC.to_vec();
+    header.extend([1, 0]);
+    header.extend((dict.len() as u16).to_le_bytes());
+    header.extend(dict.as_bytes());
+    header
+}
+
+// MiniTable targets as a `uint32` array of shape (states, 3), with the columns
+// R, U and F and row `id` for the state `id`
+pub fn write_npy_table(table: &dyn BranchTable, state_count: usize, fname: &str) -> io::Result<()> {
+    let mut f = BufWriter::new(File::create(fname)?);
+    f.write_all(&npy_header("<u4", &[state_count, 3]))?;
+    for id in 0..state_count as u32 {
+        for target in table.get_branches(id) {
+            f.write_all(&target.to_le_bytes())?;
+        }
+    }
+    f.flush()
+}
+
+// Probability vector, e.g. of an `MtableIterator`, as a `float64` array
+pub fn write_npy_probs(probs: &[f64], fname: &str) -> io::Result<()> {
+    let mut f = BufWriter::new(File::create(fname)?);
+    f.write_all(&npy_header("<f8", &[probs.len()]))?;
+    for prob in probs {
+        f.write_all(&prob.to_le_bytes())?;
+    }
+    f.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npy_header() {
+        let header = npy_header("<u4", &[3674160, 3]);
+        assert_eq!(header.len() % 64, 0);
+        assert_eq!(&header[..8], b"\x93NUMPY\x01\x00");
+        let dict = String::from_utf8(header[10..].to_vec()).unwrap();
+        assert!(
+            dict.starts_with("{'descr': '<u4', 'fortran_order': False, 'shape': (3674160, 3), }")
+        );
+        assert!(dict.ends_with('\n'));
+        let header = npy_header("<f8", &[5]);
+        assert_eq!(header.len() % 64, 0);
+        assert!(String::from_utf8(header[10..].to_vec())
+            .unwrap()
+            .contains("'shape': (5,)"));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mtable: Vec<[u32; 4]> = vec![[1, 1, 2, 0]];
+        assert_eq!(
+            get_transitions(&mtable, 0, [0.25, 0.25, 0.5]),
+            vec![(1, 0.5), (2, 0.5)]
+        );
+        assert_eq!(get_transitions(&mtable, 0, [0.0, 0.0, 1.0]), vec![(2, 1.0)]);
+    }
+}
@@ -0,0 +1,268 @@
+// Facelet string input
+//
+// The reverse of `PocketCube::to_facelets`: reads 24 face letters (U, L, F, R,
+// B, D) in the index order of Implementation.md, or in the URFDLB order of
+// other cube programs, which lists the same faces in the order U, R, F, D, L,
+// B. Both read every face in the same orientation as the net in
+// Implementation.md.
+//
+// The cube can be held in any orientation: it is rotated until the DLB cubic
+// is the heart piece, with its D, L and B colours on the D, L and B faces.
+
+use crate::enums::CubeRealSide::{self, *};
+use crate::enums::{CornerTwist, CORNER_POSITIONS, CORNER_TWISTS};
+use crate::group::{PocketCube, CORNER_FACELETS};
+use clap::ValueEnum;
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum FaceletOrder {
+    // U L F R B D, as in Implementation.md
+    Implementation,
+    Urfdlb,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FaceletError {
+    Length(usize),
+    // index in the input
+    Color {
+        index: usize,
+        color: char,
+    },
+    ColorCount {
+        color: CubeRealSide,
+        count: usize,
+    },
+    // no cubic has these colours in this clockwise order
+    Corner {
+        indexes: [usize; 3],
+        colors: [CubeRealSide; 3],
+    },
+    DuplicateCorner {
+        colors: [CubeRealSide; 3],
+    },
+    // sum of the twists of all cubics once the heart piece is in place
+    Twist(u32),
+}
+
+// Whole-cube rotations as `rotated[i] = facelets[PERM[i]]`
+pub const X_PERM: [usize; 24] = [
+    8, 9, 10, 11, 5, 7, 4, 6, 20, 21, 22, 23, 14, 12, 15, 13, 3, 2, 1, 0, 19, 18, 17, 16,
+];
+pub const Y_PERM: [usize; 24] = [
+    2, 0, 3, 1, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 4, 5, 6, 7, 21, 23, 20, 22,
+];
+
+// First facelet of every face of the implementation order in the URFDLB order
+const URFDLB_FACES: [usize; 6] = [0, 16, 8, 4, 20, 12];
+
+impl FaceletOrder {
+    // Index in the input of a facelet in the implementation order
+    fn input_index(self, index: usize) -> usize {
+        match self {
+            FaceletOrder::Implementation => index,
+            FaceletOrder::Urfdlb => URFDLB_FACES[index / 4] + index % 4,
+        }
+    }
+}
+
+fn side_from_char(c: char) -> Option<CubeRealSide> {
+    match c {
+        'R' => Some(R),
+        'L' => Some(L),
+        'U' => Some(U),
+        'D' => Some(D),
+        'F' => Some(F),
+        'B' => Some(B),
+        _ => None,
+    }
+}
+
+fn rotate(facelets: &[CubeRealSide; 24], perm: &[usize; 24]) -> [CubeRealSide; 24] {
+    std::array::from_fn(|i| facelets[perm[i]])
+}
+
+// The cubic (as its index in `PocketCube::new`) and twist that shows `colors`
+fn find_cubic(cube: &PocketCube, colors: [CubeRealSide; 3]) -> Option<(usize, CornerTwist)> {
+    for (i, default) in cube.cubics.iter().enumerate() {
+        for rot in CORNER_TWISTS {
+            let mut cubic = *default;
+            cubic.set_rot(rot);
+            if cubic.get_sides_rotated() == colors {
+                return Some((i, rot));
+            }
+        }
+    }
+    None
+}
+
+pub fn parse_facelets(text: &str, order: FaceletOrder) -> Result<PocketCube, FaceletError> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    if chars.len() != 24 {
+        return Err(FaceletError::Length(chars.len()));
+    }
+    let mut facelets = [U; 24];
+    for (i, facelet) in facelets.iter_mut().enumerate() {
+        let index = order.input_index(i);
+        *facelet = side_from_char(chars[index]).ok_or(FaceletError::Color {
+            index,
+            color: chars[index],
+        })?;
+    }
+    for color in [U, L, F, R, B, D] {
+        let count = facelets.iter().filter(|x| **x == color).count();
+        if count != 4 {
+            return Err(FaceletError::ColorCount { color, count });
+        }
+    }
+
+    // Chirality does not change with the orientation, so the cubics are checked
+    // before rotating
+    let solved = PocketCube::new();
+    let mut found = [false; 8];
+    for indexes in CORNER_FACELETS {
+        let colors = indexes.map(|x| facelets[x]);
+        let Some((i, _)) = find_cubic(&solved, colors) else {
+            return Err(FaceletError::Corner {
+                indexes: indexes.map(|x| order.input_index(x)),
+                colors,
+            });
+        };
+        if found[i] {
+            return Err(FaceletError::DuplicateCorner {
+                colors: solved.cubics[i].get_sides_rotated(),
+            });
+        }
+        found[i] = true;
+    }
+
+    // Every cubic exists once, so one of the 24 orientations has the DLB cubic
+    // in place
+    let mut queue = VecDeque::from([facelets]);
+    let mut seen = vec![facelets];
+    let facelets = loop {
+        let current = queue.pop_front().unwrap();
+        if CORNER_FACELETS[7].map(|x| current[x]) == [D, L, B] {
+            break current;
+        }
+        for perm in [&X_PERM, &Y_PERM] {
+            let rotated = rotate(&current, perm);
+            if !seen.contains(&rotated) {
+                seen.push(rotated);
+                queue.push_back(rotated);
+            }
+        }
+    };
+
+    let mut cube = PocketCube::new();
+    let mut twist_sum = 0;
+    for (pos, indexes) in CORNER_FACELETS.iter().enumerate() {
+        let (i, rot) = find_cubic(&solved, indexes.map(|x| facelets[x])).unwrap();
+        cube.cubics[i].set_pos(CORNER_POSITIONS[pos]);
+        cube.cubics[i].set_rot(rot);
+        twist_sum += u32::from(rot);
+    }
+    if twist_sum % 3 != 0 {
+        return Err(FaceletError::Twist(twist_sum));
+    }
+    Ok(cube)
+}
+
+impl fmt::Display for FaceletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaceletError::Length(length) => {
+                write!(f, "Expected 24 facelets, got {}", length)
+            }
+            FaceletError::Color { index, color } => write!(
+                f,
+                "Facelet {} is '{}', expected one of U, L, F, R, B and D",
+                index, color
+            ),
+            FaceletError::ColorCount { color, count } => write!(
+                f,
+                "Colour {} appears on {} facelets instead of 4",
+                color, count
+            ),
+            FaceletError::Corner { indexes, colors } => write!(
+                f,
+                "Facelets {}, {} and {} are {}, {} and {}, which is not a corner of the cube",
+                indexes[0], indexes[1], indexes[2], colors[0], colors[1], colors[2]
+            ),
+            FaceletError::DuplicateCorner { colors } => write!(
+                f,
+                "The {}{}{} corner appears more than once",
+                colors[0], colors[1], colors[2]
+            ),
+            FaceletError::Twist(sum) => write!(
+                f,
+                "The corner twists add up to {}, which is not a multiple of 3 (a corner is twisted in place)",
+                sum
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PermId;
+
+    const SOLVED: &str = "UUUULLLLFFFFRRRRBBBBDDDD";
+
+    fn to_string(facelets: &[CubeRealSide; 24]) -> String {
+        facelets.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for id in (0..3674160).step_by(9973) {
+            let mut pc = PocketCube::new();
+            pc.apply_id(PermId::from_id(id));
+            let facelets = pc.to_facelets().unwrap();
+            let parsed = parse_facelets(&to_string(&facelets), FaceletOrder::Implementation);
+            assert_eq!(parsed.unwrap().get_perm_id().get_id(), id);
+
+            // held in any orientation
+            let rotated = rotate(&rotate(&facelets, &X_PERM), &Y_PERM);
+            let parsed = parse_facelets(&to_string(&rotated), FaceletOrder::Implementation);
+            assert_eq!(parsed.unwrap().get_perm_id().get_id(), id);
+        }
+    }
+
+    #[test]
+    fn test_urfdlb() {
+        let pc = parse_facelets("UUUURRRRFFFFDDDDLLLLBBBB", FaceletOrder::Urfdlb).unwrap();
+        assert_eq!(pc.get_perm_id().get_id(), 0);
+        assert_eq!(
+            parse_facelets("UUUURRRRFFFFDDDDLLLLBBBX", FaceletOrder::Urfdlb).err(),
+            Some(FaceletError::Color {
+                index: 23,
+                color: 'X'
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_facelets() {
+        let parse = |text: &str| parse_facelets(text, FaceletOrder::Implementation).err();
+        assert_eq!(parse("UUU"), Some(FaceletError::Length(3)));
+        assert_eq!(
+            parse("UUUULLLLFFFFRRRRBBBBDDDU"),
+            Some(FaceletError::ColorCount { color: U, count: 5 })
+        );
+        // swapped stickers of the FUR cubic mirror it
+        assert!(matches!(
+            parse("UUUFLLLLFUFFRRRRBBBBDDDD"),
+            Some(FaceletError::Corner { .. })
+        ));
+        // FUR twisted in place
+        assert_eq!(
+            parse("UUURLLLLFUFFFRRRBBBBDDDD"),
+            Some(FaceletError::Twist(1))
+        );
+        assert!(parse(SOLVED).is_none());
+    }
+}
@@ -1,8 +1,7 @@
-use crate::enums::CornerPos::*;
 use crate::enums::CubeRealSide::*;
-use crate::enums::{CornerPos, CornerTwist, CubeRealSide};
-use crate::moves::CubicPerm;
-use crate::moves::{move_front, move_right, move_up};
+use crate::enums::{CornerPos, CornerTwist, CubeRealSide, CORNER_POSITIONS, CORNER_TWISTS};
+use crate::error::CubeError;
+use crate::moves::{Move, BASE_MOVES};
 use crate::perm::id_to_index_arr;
 use crate::perm::index_arr_to_id;
 use crate::perm::index_arr_to_permutation;
@@ -56,6 +55,24 @@ impl Cubic {
     }
 }
 
+// Facelet of every side of the cubic at each `CornerPos`, in the side order of
+// `Cubic::get_sides_rotated` (see Facelets in Implementation.md)
+pub const CORNER_FACELETS: [[usize; 3]; 8] = [
+    [3, 9, 12],   // FUR
+    [2, 5, 8],    // FUL
+    [21, 14, 11], // FDR
+    [20, 10, 7],  // FDL
+    [1, 13, 16],  // BUR
+    [0, 17, 4],   // BUL
+    [23, 18, 15], // BDR
+    [22, 6, 19],  // BDL
+];
+
+// Facelets of the solved cube, as given by `PocketCube::to_facelets`
+pub const SOLVED_FACELETS: [CubeRealSide; 24] = [
+    U, U, U, U, L, L, L, L, F, F, F, F, R, R, R, R, B, B, B, B, D, D, D, D,
+];
+
 #[derive(Clone)]
 pub struct PocketCube {
     pub cubics: [Cubic; 8],
@@ -89,14 +106,15 @@ impl PocketCube {
         for i in &rot_ids {
             rot_sum += i;
         }
-        let last_rot: CornerTwist = (((rot_sum % 3) + 3) % 3).into();
+        // the twists of all non-heart cubics always add up to a multiple of 3
+        let last_rot = CORNER_TWISTS[((3 - rot_sum % 3) % 3) as usize];
 
         for i in 0..7 {
             self.cubics[i].set_pos(pos_perm[i].get_pos());
         }
 
         for i in 0..6 {
-            self.cubics[i].set_rot(rot_ids[i].into());
+            self.cubics[i].set_rot(CORNER_TWISTS[rot_ids[i] as usize]);
         }
         self.cubics[6].set_rot(last_rot);
     }
@@ -160,17 +178,11 @@ impl PocketCube {
     }
 
     pub fn get_branches(&self) -> [PermId; 3] {
-        let (mut cube1, mut cube2, mut cube3) = (self.clone(), self.clone(), self.clone());
-
-        cube1.do_move(move_right);
-        cube2.do_move(move_up);
-        cube3.do_move(move_front);
-
-        return [
-            cube1.get_perm_id(),
-            cube2.get_perm_id(),
-            cube3.get_perm_id(),
-        ];
+        BASE_MOVES.map(|mv| {
+            let mut cube = self.clone();
+            cube.apply(mv);
+            cube.get_perm_id()
+        })
     }
 
     pub fn get_cubics(&self) -> [Cubic; 7] {
@@ -194,45 +206,32 @@ impl PocketCube {
         ];
     }
 
-    pub fn get_sides(&self, pos: CornerPos) -> [CubeRealSide; 3] {
-        for i in &self.cubics {
-            if i.get_pos() == pos {
-                return i.get_sides_rotated();
+    pub fn get_sides(&self, pos: CornerPos) -> Result<[CubeRealSide; 3], CubeError> {
+        self.cubics
+            .iter()
+            .find(|x| x.get_pos() == pos)
+            .map(|x| x.get_sides_rotated())
+            .ok_or(CubeError::MissingCubic(pos))
+    }
+
+    pub fn to_facelets(&self) -> Result<[CubeRealSide; 6 * 2 * 2], CubeError> {
+        let mut facelets = [U; 6 * 2 * 2];
+        for (pos, indexes) in CORNER_POSITIONS.into_iter().zip(CORNER_FACELETS) {
+            let sides = self.get_sides(pos)?;
+            for (index, side) in indexes.into_iter().zip(sides) {
+                facelets[index] = side;
             }
         }
-        panic!(
-            "Tried to get sides of the non-existent cubic with position {:?}",
-            pos
-        );
+        Ok(facelets)
     }
 
-    pub fn to_facelets(&self) -> [CubeRealSide; 6 * 2 * 2] {
-        let (fur, ful, fdr, fdl) = (
-            self.get_sides(FUR),
-            self.get_sides(FUL),
-            self.get_sides(FDR),
-            self.get_sides(FDL),
-        );
-        let (bur, bul, bdr, bdl) = (
-            self.get_sides(BUR),
-            self.get_sides(BUL),
-            self.get_sides(BDR),
-            self.get_sides(BDL),
-        );
-
-        return [
-            bul[0], bur[0], ful[0], fur[0], /* Up Face */
-            bul[2], ful[1], bdl[1], fdl[2], /* Left Face */
-            ful[2], fur[1], fdl[1], fdr[2], /* Front Face */
-            fur[2], bur[1], fdr[1], bdr[2], /* Right Face */
-            bur[2], bul[1], bdr[1], bdl[2], /* Back Face */
-            fdl[0], fdr[0], bdl[0], bdr[0], /* Down Face */
-        ];
+    pub fn to_facelet_string(&self) -> Result<String, CubeError> {
+        Ok(self.to_facelets()?.iter().map(|x| x.to_string()).collect())
     }
 
-    pub fn do_move(&mut self, perm_func: impl Fn(CornerPos) -> CubicPerm) {
+    pub fn apply(&mut self, mv: Move) {
         for i in &mut self.cubics {
-            let perm = perm_func(i.get_pos());
+            let perm = mv.get_perm(i.get_pos());
             i.set_pos(perm.pos);
             i.rotate(perm.rot);
         }
@@ -257,4 +256,34 @@ mod tests {
 
     // #[test]
     // fn test_index_to_
+
+    #[test]
+    fn test_apply_id_facelets() {
+        let mut pc = PocketCube::new();
+        pc.apply(Move::R);
+        pc.apply(Move::U);
+        pc.apply(Move::F);
+        pc.apply(Move::U);
+
+        let mut applied = PocketCube::new();
+        applied.apply_id(pc.get_perm_id());
+        assert_eq!(applied.to_facelets().unwrap(), pc.to_facelets().unwrap());
+    }
+
+    #[test]
+    fn test_facelet_string() {
+        let pc = PocketCube::new();
+        assert_eq!(pc.to_facelet_string().unwrap(), "UUUULLLLFFFFRRRRBBBBDDDD");
+        assert_eq!(pc.to_facelets().unwrap(), SOLVED_FACELETS);
+    }
+
+    #[test]
+    fn test_missing_cubic() {
+        let mut pc = PocketCube::new();
+        pc.cubics[1].set_pos(CornerPos::FUR);
+        assert!(matches!(
+            pc.to_facelets(),
+            Err(CubeError::MissingCubic(CornerPos::FUL))
+        ));
+    }
 }
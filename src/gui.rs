@@ -1,6 +1,7 @@
 use crate::enums::CubeRealSide;
 use crate::geom; // , PocketCube
-use crate::moves::{self, move_inv, MoveFunc};
+use crate::group::SOLVED_FACELETS;
+use crate::moves::Move;
 use crate::rubiks_cube::PocketCube;
 use three_d::*;
 // use three_d_asset::io::load;
@@ -98,7 +99,7 @@ pub fn mainloop() {
     // };
 
     let mut group_pc = crate::group::PocketCube::new();
-    let mut pocket_cube = PocketCube::new(group_pc.to_facelets());
+    let mut pocket_cube = PocketCube::new(SOLVED_FACELETS);
 
     let pc_to_model = move |pocket_cube: &PocketCube| -> Gm<Mesh, ColorMaterial> {
         let mesh = pocket_cube.get_mesh_vec();
@@ -131,36 +132,36 @@ pub fn mainloop() {
 
          if *kind == Key::R {
        	group_pc.reset_cubics();
-	           pocket_cube.set_facelets(group_pc.to_facelets());
+	           pocket_cube.set_facelets(group_pc.to_facelets().expect("The GUI cube keeps every cubic"));
 	           model = pc_to_model(&pocket_cube);
        } else if *kind == Key::T {
        	group_pc.reset_cubics();
         group_pc.cubics[0].rotate(crate::enums::CornerTwist::Rot1);
-	           pocket_cube.set_facelets(group_pc.to_facelets());
+	           pocket_cube.set_facelets(group_pc.to_facelets().expect("The GUI cube keeps every cubic"));
 	           model = pc_to_model(&pocket_cube);
        } else {
 
          let is_inverse = modifiers.shift;
-         let move_func = match *kind {
-         Key::Q => Some(moves::move_front as MoveFunc),
-         Key::W => Some(moves::move_up as MoveFunc),
-         Key::E => Some(moves::move_right as MoveFunc),
+         let mv = match *kind {
+         Key::Q => Some(Move::F),
+         Key::W => Some(Move::U),
+         Key::E => Some(Move::R),
          _ => None,
          };
-         if let Some(move_func) = move_func {
+         if let Some(mv) = mv {
          		if is_inverse {
-	           group_pc.do_move(move_inv(move_func));
+	           group_pc.apply(mv.inverse());
         } else {
-	           group_pc.do_move(move_func);
+	           group_pc.apply(mv);
         }
-	           pocket_cube.set_facelets(group_pc.to_facelets());
+	           pocket_cube.set_facelets(group_pc.to_facelets().expect("The GUI cube keeps every cubic"));
 	           model = pc_to_model(&pocket_cube);
 				println!("{:?}", group_pc.get_branches());
 				let id = group_pc.get_perm_id().get_id();
 				println!("Cube ID: {}", id);
 
 				let mut pc = crate::group::PocketCube::new();
-				pc.apply_id(id.into());
+				pc.apply_id(crate::types::PermId::from_id(id));
 				println!("AgainID: {}", pc.get_perm_id().get_id());
 
 				println!("\n");
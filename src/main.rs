@@ -1,63 +1,858 @@
 // #![allow(warnings)]
 
-use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
 
-use calc::MtableIterator;
+use algorithm::Algorithm;
+use calc::{BranchTable, MtableIterator};
+use clap::Parser;
+use cli::{CalcArgs, Cli, Command, ExportFormat, DEFAULT_TABLE};
+use config::{ExperimentConfig, Initial};
 use csv::Writer;
+use enums::{CORNER_POSITIONS, CORNER_TWISTS};
+use error::CubeError;
+use facelets::{parse_facelets, FaceletOrder};
+use notation::MoveSequence;
+use packed::AnyMiniTable;
+use schreier::{FaceletPerm, StabilizerChain};
+use state::CubeState;
+use subgroup::Subgroup;
+use types::{PermId, PERMID_COUNT};
 
+mod algorithm;
 mod calc;
+mod classes;
+mod cli;
+mod config;
 mod enums;
+mod error;
+mod export;
+mod facelets;
+mod factored;
 mod geom;
 mod group;
 mod gui;
+mod marginal;
 mod moves;
+mod notation;
+mod packed;
 mod perm;
 mod rubiks_cube;
+mod schedule;
+mod schreier;
+mod search;
+mod solver;
+mod state;
+mod subgroup;
+mod table;
 mod types;
+mod verify;
+
+// States compared against the cube model by `verify` without `--all`
+const VERIFY_SAMPLE_STRIDE: usize = 97;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() >= 2 && args[1] == "gen" {
-        let mtable = calc::generate_mini_table();
-        calc::write_to_file(&mtable, "out/output.tbl");
-    } else if args.len() >= 2 && (args[1] == "calc" || args[1] == "calcdis") {
-        let mtable = calc::load_mini_table("out/output.tbl");
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Gen { table }) => {
+            let mtable = calc::generate_mini_table();
+            or_exit(
+                calc::write_to_file(&mtable, &table),
+                &format!("Unable to write table {}", table),
+            );
+        }
+        Some(Command::Calc(args)) => calc(args),
+        Some(Command::Calcdis(args)) => calc(CalcArgs {
+            disperse: true,
+            ..args
+        }),
+        Some(Command::Convert {
+            input,
+            output,
+            format,
+        }) => {
+            let table = or_exit(
+                AnyMiniTable::load(&input),
+                &format!("Unable to load table {}", input),
+            );
+            or_exit(
+                table.into_format(format).write_to_file(&output),
+                &format!("Unable to write table {}", output),
+            );
+        }
+        Some(Command::Export {
+            format,
+            output,
+            table,
+        }) => {
+            let table = load_table(&table);
+            // mtx uses the uniform R, U, F walk
+            let result = match format {
+                ExportFormat::Mtx => export::write_matrix_market(
+                    table.as_ref(),
+                    PERMID_COUNT,
+                    [1.0 / 3.0; 3],
+                    &output,
+                ),
+                ExportFormat::Npy => export::write_npy_table(table.as_ref(), PERMID_COUNT, &output),
+                ExportFormat::Edges => {
+                    export::write_edge_list(table.as_ref(), PERMID_COUNT, &output)
+                }
+            };
+            or_exit(result, &format!("Unable to write {}", output));
+        }
+        Some(Command::Verify { table, all }) => verify(&table, all),
+        Some(Command::Marginal {
+            compare,
+            table,
+            output,
+            iterations,
+        }) => marginal(compare, &table, &output, iterations),
+        Some(Command::Cubic { output, iterations }) => cubic(&output, iterations),
+        Some(Command::Solver {
+            p,
+            start,
+            table,
+            output,
+            summary,
+            iterations,
+        }) => solver(p, start, &table, &output, &summary, iterations),
+        Some(Command::Scramble {
+            table,
+            output,
+            iterations,
+        }) => scramble(&table, &output, iterations),
+        Some(Command::State { moves }) => {
+            let text = moves.join(" ");
+            let sequence = MoveSequence::parse(&text).unwrap_or_else(|e| {
+                eprintln!("{}\n{}\n{}", e, text, e.pointer());
+                process::exit(1);
+            });
+            let mut pc = group::PocketCube::new();
+            sequence.apply(&mut pc);
+            println!("PermId: {}", pc.get_perm_id().get_id());
+            println!("Facelets: {}", or_exit(pc.to_facelet_string(), "Facelets"));
+        }
+        Some(Command::Alg {
+            algorithm,
+            mirror,
+            inverse,
+        }) => alg(&algorithm, mirror, inverse),
+        Some(Command::Relative { from, to }) => relative(&from, &to),
+        Some(Command::Cycles {
+            moves,
+            facelets,
+            order,
+            id,
+        }) => {
+            let state = or_exit(
+                read_state(moves.as_deref(), facelets.as_deref(), order, id),
+                "Invalid state",
+            );
+            print_cycles(state);
+        }
+        Some(Command::Classes { output }) => classes(&output),
+        Some(Command::SchreierSims { generators, member }) => {
+            schreier_sims(&generators, member.as_deref())
+        }
+        Some(Command::Subgroup {
+            generators,
+            output,
+            walk,
+        }) => subgroup(&generators, &output, walk),
+        Some(Command::Facelets { facelets, order }) => {
+            let pc = parse_facelets(&facelets, order).unwrap_or_else(|e| {
+                eprintln!("Invalid facelets: {}", e);
+                process::exit(1);
+            });
+            println!("PermId: {}", pc.get_perm_id().get_id());
+            println!("Facelets: {}", or_exit(pc.to_facelet_string(), "Facelets"));
+        }
+        Some(Command::Gui) | None => gui::mainloop(),
+    }
+}
+
+fn alg(text: &str, mirror: bool, inverse: bool) {
+    let mut algorithm = Algorithm::parse(text).unwrap_or_else(|e| {
+        eprintln!("{}\n{}\n{}", e, text, e.pointer());
+        process::exit(1);
+    });
+    if mirror {
+        algorithm = algorithm.mirror();
+    }
+    if inverse {
+        algorithm = algorithm.inverse();
+    }
+    let simplified = algorithm.simplify();
+    println!("Expanded ({} turns): {}", algorithm.len(), algorithm);
+    if simplified.is_empty() {
+        println!("Simplified: the turns cancel out");
+    } else {
+        println!("Simplified ({} turns): {}", simplified.len(), simplified);
+    }
+    let state = CubeState::from(algorithm.get_perm_id());
+    println!("PermId: {}", state.get_perm_id().get_id());
+    println!("Order: {}", state.order());
+    println!(
+        "Facelets: {}",
+        or_exit(state.to_pocket_cube().to_facelet_string(), "Facelets")
+    );
+}
+
+fn relative(from: &str, to: &str) {
+    let parse = |text: &str| {
+        let algorithm = Algorithm::parse(text).unwrap_or_else(|e| {
+            eprintln!("{}\n{}\n{}", e, text, e.pointer());
+            process::exit(1);
+        });
+        CubeState::from(algorithm.get_perm_id())
+    };
+    let state = parse(from).to(&parse(to));
+    println!("PermId: {}", state.get_perm_id().get_id());
+    println!("Order: {}", state.order());
+    println!(
+        "Facelets: {}",
+        or_exit(state.to_pocket_cube().to_facelet_string(), "Facelets")
+    );
+}
+
+// The state given by exactly one of the options of `cycles`
+fn read_state(
+    moves: Option<&str>,
+    facelets: Option<&str>,
+    order: FaceletOrder,
+    id: Option<u32>,
+) -> Result<CubeState, CubeError> {
+    let perm_id = match (moves, facelets, id) {
+        (Some(moves), _, _) => Algorithm::parse(moves)?.get_perm_id(),
+        (_, Some(facelets), _) => parse_facelets(facelets, order)?.get_perm_id(),
+        (_, _, Some(id)) => PermId::try_from(id)?,
+        _ => unreachable!("clap requires one of the options"),
+    };
+    Ok(CubeState::from(perm_id))
+}
+
+fn print_cycles(state: CubeState) {
+    let cycle_type: Vec<String> = state
+        .get_cycle_type()
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    println!("PermId: {}", state.get_perm_id().get_id());
+    println!("Cycles: {}", state);
+    for cycle in state.get_cycles() {
+        if cycle.positions.len() > 1 || cycle.twist != 0 {
+            println!(
+                "  {} length {}, twist {}",
+                cycle,
+                cycle.positions.len(),
+                cycle.twist
+            );
+        }
+    }
+    println!("Cycle type: {}", cycle_type.join(" "));
+    println!("Parity: {}", if state.is_even() { "even" } else { "odd" });
+    println!("Order: {}", state.order());
+}
+
+fn classes(output: &str) {
+    println!("Computing conjugacy classes...");
+    let classes = classes::conjugacy_classes();
+    let mut wtr = create_csv(output);
+    wtr.write_record([
+        "class",
+        "size",
+        "representative",
+        "order",
+        "cycles",
+        "cycle_type",
+    ])
+    .unwrap();
+    for (i, class) in classes.iter().enumerate() {
+        let cycle_type: Vec<String> = class
+            .representative
+            .get_cycle_type()
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        wtr.write_record([
+            i.to_string(),
+            class.size.to_string(),
+            class.representative.get_perm_id().get_id().to_string(),
+            class.order.to_string(),
+            class.representative.to_string(),
+            cycle_type.join(" "),
+        ])
+        .unwrap();
+    }
+    wtr.flush().unwrap();
+
+    let mut wtr = create_csv(&sibling_path(output, "_orders.csv"));
+    wtr.write_record(["order", "states", "classes"]).unwrap();
+    for (order, states, class_count) in classes::order_distribution(&classes) {
+        println!(
+            "Order {}: {} states in {} classes",
+            order, states, class_count
+        );
+        wtr.write_record([
+            order.to_string(),
+            states.to_string(),
+            class_count.to_string(),
+        ])
+        .unwrap();
+    }
+    wtr.flush().unwrap();
+    let total: usize = classes.iter().map(|x| x.size).sum();
+    println!("{} classes, {} states", classes.len(), total);
+}
+
+// Cycle notation of facelets if the text has no moves, like `(0 1 3 2)`, with
+// the algorithm if it is one
+fn parse_facelet_perm(text: &str) -> Result<(FaceletPerm, Option<Algorithm>), CubeError> {
+    if text
+        .chars()
+        .all(|c| c.is_ascii_digit() || c.is_whitespace() || "(),".contains(c))
+    {
+        return Ok((FaceletPerm::parse(text)?, None));
+    }
+    let algorithm = Algorithm::parse(text)?;
+    Ok((FaceletPerm::from_algorithm(&algorithm), Some(algorithm)))
+}
+
+fn schreier_sims(generators: &[String], member: Option<&str>) {
+    let (perms, algorithms): (Vec<FaceletPerm>, Vec<Option<Algorithm>>) = generators
+        .iter()
+        .map(|x| or_exit(parse_facelet_perm(x), x))
+        .unzip();
+    let chain = StabilizerChain::new(&perms);
+    println!("Order: {}", chain.order());
+    let base: Vec<String> = chain.get_base().iter().map(|x| x.to_string()).collect();
+    println!("Base: {}", base.join(" "));
+    let lengths: Vec<String> = chain
+        .get_orbit_lengths()
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    println!("Orbit lengths: {}", lengths.join(" "));
+    println!("Strong generators:");
+    for (perm, word) in chain.get_strong_generators() {
+        println!("  {} ({} factors)", perm, word.len());
+    }
+
+    if let Some(text) = member {
+        let (target, _) = or_exit(parse_facelet_perm(text), text);
+        let facelets: String = target.to_facelets().iter().map(|x| x.to_string()).collect();
+        println!("Facelets: {}", facelets);
+        if !chain.contains(&target) {
+            println!("{} is not in the group", text);
+            return;
+        }
+        println!("{} is in the group", text);
+        let word = chain.factorize(&target).unwrap();
+        println!("Word: {}", format_word(&word, generators));
+        // only if every generator in the word is an algorithm
+        let mut algorithm = Some(Algorithm::new(Vec::new()));
+        for (i, exponent) in &word {
+            algorithm = algorithm
+                .zip(algorithms[*i].as_ref())
+                .map(|(algorithm, factor)| {
+                    let factor = if *exponent < 0 {
+                        factor.inverse()
+                    } else {
+                        factor.clone()
+                    };
+                    algorithm.concat(&factor.repeat(exponent.unsigned_abs() as usize))
+                });
+        }
+        if let Some(algorithm) = algorithm {
+            println!("Algorithm: {}", algorithm.simplify());
+        }
+    }
+}
+
+// Generators are written as `(text)`, followed by `^` and their exponent unless
+// it is 1
+fn format_word(word: &schreier::Word, generators: &[String]) -> String {
+    if word.is_empty() {
+        return "()".to_string();
+    }
+    let factors: Vec<String> = word
+        .iter()
+        .map(|(i, exponent)| match exponent {
+            1 => format!("({})", generators[*i]),
+            _ => format!("({})^{}", generators[*i], exponent),
+        })
+        .collect();
+    factors.join(" ")
+}
+
+// An algorithm, or a `PermId` if the text is a number
+fn parse_generator(text: &str) -> Result<CubeState, CubeError> {
+    let perm_id = match text.parse::<u32>() {
+        Ok(id) => PermId::try_from(id)?,
+        Err(_) => Algorithm::parse(text)?.get_perm_id(),
+    };
+    Ok(CubeState::from(perm_id))
+}
+
+fn subgroup(generators: &[String], output: &str, walk: Option<usize>) {
+    let states = generators
+        .iter()
+        .map(|x| or_exit(parse_generator(x), x))
+        .collect();
+    if walk.is_some() && generators.len() > 3 {
+        eprintln!("The walk supports at most 3 generators");
+        process::exit(1);
+    }
+    let subgroup = Subgroup::generate(states);
+    println!("Order: {}", subgroup.order());
+
+    let mut wtr = create_csv(output);
+    wtr.write_record(["depth", "states"]).unwrap();
+    for (depth, count) in search::distance_histogram(subgroup.get_depths())
+        .iter()
+        .enumerate()
+    {
+        println!("Depth {}: {}", depth, count);
+        wtr.write_record([depth.to_string(), count.to_string()])
+            .unwrap();
+    }
+    wtr.flush().unwrap();
+
+    if let Some(steps) = walk {
+        let mut iterator = subgroup.create_iterator().unwrap();
+        let mut wtr = create_csv(&sibling_path(output, "_walk.csv"));
+        wtr.write_record(["loop", "value"]).unwrap();
+        for i in 0..steps {
+            // the first step always leaves the solved state, as in `calc`
+            iterator.iterate(i == 0);
+            let prob = or_exit(iterator.get_prob(0), "Solved state");
+            wtr.write_record([i.to_string(), prob.to_string()]).unwrap();
+        }
+        wtr.flush().unwrap();
+    }
+}
+
+fn calc(args: CalcArgs) {
+    // `--config <file>` describes the whole run, otherwise it is built from the options
+    let mut config = match &args.config {
+        Some(fname) => or_exit(ExperimentConfig::load(fname), fname),
+        None => ExperimentConfig {
+            steps: args.iterations,
+            moves: args.moves.clone(),
+            schedule: args.schedule.clone(),
+            initial: Initial::States(vec![match &args.scramble {
+                Some(sequence) => {
+                    let mut pc = group::PocketCube::new();
+                    sequence.apply(&mut pc);
+                    pc.get_perm_id().get_id()
+                }
+                None => args.start,
+            }]),
+            absorbing: vec![0],
+            ..ExperimentConfig::default()
+        },
+    };
+    // `calcdis` disperses from every state, also on top of a config
+    if args.disperse {
+        config.absorbing.clear();
+    }
+    let table_name = args
+        .table
+        .as_deref()
+        .or(config.table.as_deref())
+        .unwrap_or(DEFAULT_TABLE);
+    let output = config.output.as_ref().unwrap_or(&args.output);
+
+    let mut iterator = MtableIterator::new();
+    // `--factored` computes the rows from small move tables, no table file needed
+    // `--mmap` reads the rows straight from the mapped table file
+    let table: Box<dyn BranchTable> = if args.factored {
+        Box::new(factored::FactoredTable::new())
+    } else if args.mmap {
+        let table = or_exit(
+            calc::MappedMiniTable::open(table_name),
+            &format!("Unable to map table {}", table_name),
+        );
+        // `--checksum` reads the whole mapping once to validate it
+        if args.checksum {
+            or_exit(
+                table.verify_checksum(),
+                &format!("Invalid table {}", table_name),
+            );
+        }
+        println!("{:?}", table.get_row(13));
+        Box::new(table)
+    } else {
+        // `--format padded|packed|auto` picks the in-memory row format,
+        // `auto` benchmarks both. The file's own format is kept by default.
+        let table = or_exit(
+            AnyMiniTable::load(table_name),
+            &format!("Unable to load table {}", table_name),
+        );
+        let table = match args.format.map(|x| x.to_format()) {
+            Some(None) => table.into_fastest(),
+            Some(Some(format)) => table.into_format(format),
+            None => table,
+        };
+        println!("Loading mtable ({:?})...", table.get_format());
+        let table = table.into_branch_table();
+        println!("{:?}", table.get_branches(13));
+        table
+    };
+    let targets = config.resolve_targets(table.as_ref());
+    iterator.load_table(table);
+    match &config.initial {
+        Initial::Solved => iterator.set_state(0),
+        Initial::Uniform => iterator.set_uniform(),
+        Initial::States(ids) => iterator.set_distribution(ids),
+    }
+    iterator.set_absorbing(&config.absorbing);
+    println!("Loaded mtable");
+
+    // `schedule` changes the move weights over time, `moves` fixes them
+    let schedule = or_exit(config.get_schedule(), "Invalid schedule");
+
+    let mut wtr = create_csv(output);
+    let columns = config.get_columns();
+    wtr.write_record(&columns).unwrap();
+    // the config is kept with the results to make them reproducible
+    if let Some(fname) = &args.config {
+        or_exit(
+            copy_next_to(fname, output),
+            &format!("Unable to copy {}", fname),
+        );
+    }
+
+    // `--top <k>` dumps the k most probable states of every step
+    let mut top_wtr = args.top.map(|_| {
+        let mut wtr = create_csv(&sibling_path(output, "_top.csv"));
+        wtr.write_record(["loop", "rank", "id", "value", "facelets"])
+            .unwrap();
+        wtr
+    });
+
+    for i in 0..config.steps {
+        let disperse = config.absorbing.is_empty() || i == 0;
+        let entry = or_exit(
+            iterator
+                .iterate_schedule(&schedule, disperse)
+                .ok_or("no entry for this step"),
+            &format!("Schedule does not cover step {}", i),
+        )
+        .to_string();
+        let prob = or_exit(iterator.get_prob(0), "Solved state");
+        println!("Iteration {} completed", i);
+        println!("New probability = {}", prob);
+        let record: Vec<String> = columns
+            .iter()
+            .map(|column| match *column {
+                "loop" => i.to_string(),
+                "value" => prob.to_string(),
+                "disperse" => if disperse { "yes" } else { "no" }.to_string(),
+                "schedule" => entry.clone(),
+                name => {
+                    let index = config.targets.iter().position(|x| x.name == name).unwrap();
+                    let probs = iterator.get_probs();
+                    let target_prob: f64 = targets[index].iter().map(|x| probs[*x as usize]).sum();
+                    target_prob.to_string()
+                }
+            })
+            .collect();
+        wtr.write_record(&record).unwrap();
+        wtr.flush().unwrap();
+
+        // `--npy 0,10,99` saves the distribution after those steps as
+        // probs_<step>.npy next to the output
+        if args.npy.contains(&i) {
+            let fname = sibling_path(output, &format!("probs_{}.npy", i));
+            or_exit(
+                export::write_npy_probs(iterator.get_probs(), &fname),
+                &format!("Unable to write {}", fname),
+            );
+        }
+
+        if let (Some(k), Some(top_wtr)) = (args.top, top_wtr.as_mut()) {
+            let mut pc = group::PocketCube::new();
+            for (rank, (id, prob)) in iterator.top_k(k).iter().enumerate() {
+                pc.apply_id(PermId::from_id(*id));
+                top_wtr
+                    .write_record(&[
+                        i.to_string(),
+                        rank.to_string(),
+                        id.to_string(),
+                        prob.to_string(),
+                        or_exit(pc.to_facelet_string(), "Facelets"),
+                    ])
+                    .unwrap();
+            }
+            top_wtr.flush().unwrap();
+        }
+    }
+}
+
+fn verify(fname: &str, all: bool) {
+    let (table, checksum) = or_exit(
+        AnyMiniTable::load_unchecked(fname),
+        &format!("Unable to load table {}", fname),
+    );
+    let mut passed = true;
+    match checksum {
+        Ok(()) => println!("checksum: ok"),
+        Err(e) => {
+            println!("checksum: FAILED ({})", e);
+            passed = false;
+        }
+    }
+
+    let stride = if all { 1 } else { VERIFY_SAMPLE_STRIDE };
+    let table = table.into_branch_table();
+    for report in verify::verify_table(table.as_ref(), PERMID_COUNT, stride) {
+        if report.passed() {
+            println!("{}: ok ({} checked)", report.name, report.checked);
+            continue;
+        }
+        passed = false;
+        println!(
+            "{}: FAILED ({} of {} checked)",
+            report.name, report.failed, report.checked
+        );
+        for (id, message) in &report.examples {
+            println!("    row {}: {}", id, message);
+        }
+    }
+    if !passed {
+        process::exit(1);
+    }
+}
+
+fn marginal(compare: bool, table: &str, output: &str, iterations: usize) {
+    // `--compare` also runs the full chain, which needs the table file
+    let mut full = if compare {
         let mut iterator = MtableIterator::new();
-        println!("{:?}", mtable[13]);
-        println!("Loading mtable...");
-        iterator.load_mtable(mtable);
+        iterator.load_table(load_table(table));
         iterator.set_zero();
-        println!("Loaded mtable");
-
-        let mut wtr = Writer::from_path("out/output.csv").unwrap();
-        wtr.write_record(&["loop", "value", "disperse"]).unwrap();
-
-        for i in 0..100 {
-            let disperse = if args[1] == "calcdis" {
-                true
-            } else {
-                if i == 0 {
-                    true
-                } else {
-                    false
+        Some((iterator, marginal::generate_twist_ids()))
+    } else {
+        None
+    };
+
+    let mut pos_chain = marginal::MarginalChain::positions();
+    let mut twist_chain = marginal::MarginalChain::twists();
+    pos_chain.set_zero();
+    twist_chain.set_zero();
+
+    let mut wtr = create_csv(output);
+    let mut header = vec!["loop", "pos", "twist", "product"];
+    if compare {
+        header.extend(["full", "max_abs_diff", "total_variation"]);
+    }
+    wtr.write_record(&header).unwrap();
+
+    for i in 0..iterations {
+        pos_chain.iterate();
+        twist_chain.iterate();
+        let (pos_prob, twist_prob) = (pos_chain.get_prob(0), twist_chain.get_prob(0));
+        let mut record = vec![
+            i.to_string(),
+            pos_prob.to_string(),
+            twist_prob.to_string(),
+            (pos_prob * twist_prob).to_string(),
+        ];
+        if let Some((iterator, twist_ids)) = full.as_mut() {
+            iterator.iterate(true);
+            let comparison =
+                marginal::compare_marginals(iterator, &pos_chain, &twist_chain, twist_ids);
+            record.extend([
+                comparison.full_solved.to_string(),
+                comparison.max_abs_diff.to_string(),
+                comparison.total_variation.to_string(),
+            ]);
+        }
+        wtr.write_record(&record).unwrap();
+    }
+    wtr.flush().unwrap();
+}
+
+fn cubic(output: &str, iterations: usize) {
+    let mut wtr = create_csv(output);
+    wtr.write_record(["loop", "cubic", "position", "twist", "value"])
+        .unwrap();
+
+    // every cubic but the heart piece
+    for cubic_pos in &CORNER_POSITIONS[..7] {
+        let mut chain = marginal::MarginalChain::cubic(*cubic_pos);
+        for i in 0..iterations {
+            chain.iterate();
+            for pos in &CORNER_POSITIONS[..7] {
+                for twist in CORNER_TWISTS {
+                    let id = marginal::cubic_state_id(*pos, twist);
+                    wtr.write_record(&[
+                        i.to_string(),
+                        cubic_pos.to_string(),
+                        pos.to_string(),
+                        u32::from(twist).to_string(),
+                        chain.get_prob(id).to_string(),
+                    ])
+                    .unwrap();
                 }
-            };
-            iterator.iterate(disperse);
-            let prob = iterator.get_prob(0);
-            println!("Iteration {} completed", i);
-            println!("New probability = {}", prob);
-            let record_loop = i.to_string();
-            let record_prob = prob.to_string();
-            let record_disperse = if disperse {
-                "yes".to_string()
-            } else {
-                "no".to_string()
-            };
-            wtr.write_record(&[record_loop, record_prob, record_disperse])
+            }
+        }
+    }
+    wtr.flush().unwrap();
+}
+
+fn solver(
+    ps: Vec<f64>,
+    start: Option<u32>,
+    table: &str,
+    output: &str,
+    summary_output: &str,
+    iterations: usize,
+) {
+    let mtable = load_table(table);
+    println!("Computing distances...");
+    let distances = search::compute_distances(mtable.as_ref(), PERMID_COUNT);
+    let neighbors = search::generate_neighbor_table(mtable.as_ref(), PERMID_COUNT);
+    drop(mtable);
+    println!("Computed distances");
+    for (distance, count) in search::distance_histogram(&distances).iter().enumerate() {
+        println!("Distance {}: {} states", distance, count);
+    }
+
+    // optimal move probabilities 0, 0.1, ..., 1 unless `--p` picks some
+    let ps: Vec<f64> = if ps.is_empty() {
+        (0..=10).map(|x| x as f64 / 10.0).collect()
+    } else {
+        ps
+    };
+
+    let mut wtr = create_csv(output);
+    wtr.write_record(["p", "loop", "solved"]).unwrap();
+    let mut summary_wtr = create_csv(summary_output);
+    summary_wtr
+        .write_record(["p", "expected_length", "remaining"])
+        .unwrap();
+
+    for p in ps {
+        let mut noisy_solver = or_exit(
+            solver::NoisySolver::new(&neighbors, &distances, p),
+            &format!("Invalid table {}", table),
+        );
+        // `--start <id>` starts from a single state instead of a random one
+        match start {
+            Some(id) => noisy_solver.set_state(id),
+            None => noisy_solver.set_uniform(),
+        }
+        let summary = noisy_solver.run(iterations);
+        for (i, solved) in summary.solved.iter().enumerate() {
+            wtr.write_record(&[p.to_string(), i.to_string(), solved.to_string()])
                 .unwrap();
-            wtr.flush().unwrap();
         }
+        summary_wtr
+            .write_record(&[
+                p.to_string(),
+                summary.expected_length.to_string(),
+                summary.remaining.to_string(),
+            ])
+            .unwrap();
+        println!("p = {} completed", p);
+    }
+    wtr.flush().unwrap();
+    summary_wtr.flush().unwrap();
+}
+
+fn scramble(table: &str, output: &str, iterations: usize) {
+    // joint distribution of the walk length and the optimal distance of its endpoint
+    let table = load_table(table);
+    let distances = search::compute_distances(table.as_ref(), PERMID_COUNT);
+    let max_distance = search::distance_histogram(&distances).len() - 1;
+    let mut iterator = MtableIterator::new();
+    iterator.load_table(table);
+    iterator.set_zero();
+
+    let mut wtr = create_csv(output);
+    let mut header = vec!["moves".to_string()];
+    header.extend((0..=max_distance).map(|d| format!("d{}", d)));
+    header.push("expected_distance".to_string());
+    wtr.write_record(&header).unwrap();
+
+    for i in 0..=iterations {
+        if i > 0 {
+            iterator.iterate(true);
+        }
+        let distribution = search::distance_distribution(iterator.get_probs(), &distances);
+        let expected: f64 = distribution
+            .iter()
+            .enumerate()
+            .map(|(d, prob)| d as f64 * prob)
+            .sum();
+        let mut record = vec![i.to_string()];
+        record.extend((0..=max_distance).map(|d| distribution.get(d).unwrap_or(&0.0).to_string()));
+        record.push(expected.to_string());
+        wtr.write_record(&record).unwrap();
+        println!(
+            "{} moves: optimal with probability {}, {} moves wasted on average",
+            i,
+            distribution.get(i).unwrap_or(&0.0),
+            i as f64 - expected
+        );
+    }
+    wtr.flush().unwrap();
+}
+
+// File next to `fname`, e.g. out/output_top.csv for out/output.csv and "_top.csv"
+// or out/probs_0.npy for "probs_0.npy"
+fn sibling_path(fname: &str, name: &str) -> String {
+    let path = Path::new(fname);
+    let name = if name.starts_with('_') {
+        let stem = path.file_stem().map_or("".into(), |x| x.to_string_lossy());
+        format!("{}{}", stem, name)
     } else {
-        gui::mainloop();
+        name.to_string()
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+// Creates the directory of the file as well
+fn create_csv(fname: &str) -> Writer<fs::File> {
+    if let Some(dir) = Path::new(fname).parent() {
+        or_exit(
+            fs::create_dir_all(dir),
+            &format!("Unable to create directory {}", dir.display()),
+        );
     }
+    or_exit(
+        Writer::from_path(fname),
+        &format!("Unable to create {}", fname),
+    )
+}
+
+// Copies `fname` into the directory of `output`, unless it is already there
+fn copy_next_to(fname: &str, output: &str) -> io::Result<()> {
+    let file_name = Path::new(fname).file_name().unwrap_or_default();
+    let target = Path::new(output).with_file_name(file_name);
+    if target.exists() && fs::canonicalize(&target)? == fs::canonicalize(fname)? {
+        return Ok(());
+    }
+    fs::copy(fname, target).map(|_| ())
+}
+
+// A MiniTable file in either format
+fn load_table(fname: &str) -> Box<dyn BranchTable> {
+    or_exit(
+        AnyMiniTable::load(fname),
+        &format!("Unable to load table {}", fname),
+    )
+    .into_branch_table()
+}
+
+fn or_exit<T, E: fmt::Display>(result: Result<T, E>, context: &str) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}: {}", context, e);
+        process::exit(1);
+    })
 }
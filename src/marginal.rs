@@ -0,0 +1,228 @@
+// Exact marginal chains of the pocket cube walk
+//
+// Moves act on corner positions independently of the twists, so the chain over
+// `PosId`s is exact on its own. Twists are only Markovian when they are indexed
+// by position (the twist of whichever cubic currently sits there) rather than
+// by cubic like in `RotId`, so the twist chain uses that coordinate instead.
+// A single cubic is tracked by its position and twist (7 x 3 states).
+
+use crate::calc::MtableIterator;
+use crate::enums::{CornerPos, CornerTwist, CORNER_POSITIONS};
+use crate::group::PocketCube;
+use crate::moves::BASE_MOVES;
+use crate::perm::smushed_to_array;
+use crate::types::{PermId, PosId, RotId, PERMID_COUNT, PERMID_POSCOUNT, PERMID_ROTCOUNT};
+
+type MarginalTable = Vec<[u32; 3]>;
+
+pub struct MarginalChain {
+    table: MarginalTable,
+    probs: Vec<f64>,
+}
+
+pub struct MarginalComparison {
+    pub full_solved: f64,
+    pub max_abs_diff: f64,
+    pub total_variation: f64,
+}
+
+pub fn generate_pos_table() -> MarginalTable {
+    let mut table: MarginalTable = vec![[0, 0, 0]; PERMID_POSCOUNT as usize];
+    let mut pc = PocketCube::new();
+    for pid in 0..PERMID_POSCOUNT {
+        pc.apply_id(PermId::new(&PosId::new(pid), &RotId::new(0)));
+        let branches = pc.get_branches();
+        for j in 0..3 {
+            table[pid as usize][j] = branches[j].get_pos_id().get_id();
+        }
+    }
+    table
+}
+
+pub fn generate_twist_table() -> MarginalTable {
+    let mut table: MarginalTable = vec![[0, 0, 0]; PERMID_ROTCOUNT as usize];
+    for tid in 0..PERMID_ROTCOUNT {
+        let twists = id_to_twists(tid);
+        for j in 0..3 {
+            let mut moved = [0; 7];
+            for pos in 0..7 {
+                let perm = BASE_MOVES[j].get_perm(CORNER_POSITIONS[pos]);
+                let new_pos: u32 = perm.pos.into();
+                let rot: u32 = perm.rot.into();
+                moved[new_pos as usize] = (twists[pos] + rot) % 3;
+            }
+            table[tid as usize][j] = twists_to_id(&moved);
+        }
+    }
+    table
+}
+
+// State of a single cubic: position * 3 + twist
+pub fn generate_cubic_table() -> MarginalTable {
+    let mut table: MarginalTable = vec![[0, 0, 0]; 7 * 3];
+    for pos in 0..7 {
+        for twist in 0..3 {
+            for j in 0..3 {
+                let perm = BASE_MOVES[j].get_perm(CORNER_POSITIONS[pos as usize]);
+                let new_pos: u32 = perm.pos.into();
+                let rot: u32 = perm.rot.into();
+                table[(pos * 3 + twist) as usize][j] = new_pos * 3 + (twist + rot) % 3;
+            }
+        }
+    }
+    table
+}
+
+pub fn cubic_state_id(pos: CornerPos, twist: CornerTwist) -> u32 {
+    let (pos, twist): (u32, u32) = (pos.into(), twist.into());
+    pos * 3 + twist
+}
+
+// Twists of the positions FUR..BDR, the last one is implied by the others
+pub fn id_to_twists(tid: u32) -> [u32; 7] {
+    let digits = smushed_to_array::<u32, 6>(tid, |x: u32| x);
+    let mut twists = [0; 7];
+    twists[..6].copy_from_slice(&digits);
+    twists[6] = (3 - digits.iter().sum::<u32>() % 3) % 3;
+    twists
+}
+
+pub fn twists_to_id(twists: &[u32; 7]) -> u32 {
+    twists[..6].iter().fold(0, |sum, x| sum * 3 + x)
+}
+
+// Position-indexed twist coordinate of a full state, as used by the twist chain
+pub fn perm_id_to_twist_id(pc: &mut PocketCube, id: PermId) -> u32 {
+    pc.apply_id(id);
+    let mut twists = [0; 7];
+    for cubic in pc.get_cubics() {
+        let pos: u32 = cubic.get_pos().into();
+        twists[pos as usize] = cubic.get_rot().into();
+    }
+    twists_to_id(&twists)
+}
+
+impl MarginalChain {
+    pub fn new(table: MarginalTable) -> Self {
+        let probs = vec![0.0; table.len()];
+        Self { table, probs }
+    }
+
+    pub fn positions() -> Self {
+        Self::new(generate_pos_table())
+    }
+
+    pub fn twists() -> Self {
+        Self::new(generate_twist_table())
+    }
+
+    // Tracks the given cubic, starting from its solved position
+    pub fn cubic(pos: CornerPos) -> Self {
+        let mut chain = Self::new(generate_cubic_table());
+        chain.set_state(cubic_state_id(pos, CornerTwist::Rot0));
+        chain
+    }
+
+    pub fn set_zero(&mut self) {
+        self.set_state(0);
+    }
+
+    pub fn set_state(&mut self, id: u32) {
+        self.probs.iter_mut().for_each(|x| *x = 0.0);
+        self.probs[id as usize] = 1.0;
+    }
+
+    pub fn iterate(&mut self) {
+        let mut new_probs: Vec<f64> = vec![0.0; self.probs.len()];
+        for i in 0..self.probs.len() {
+            let prob_each = self.probs[i] / 3.0;
+            for j in 0..3 {
+                new_probs[self.table[i][j] as usize] += prob_each;
+            }
+        }
+        self.probs = new_probs;
+    }
+
+    pub fn get_prob(&self, id: u32) -> f64 {
+        self.probs[id as usize]
+    }
+}
+
+// Compares the full chain with the product of the two marginals over all states.
+// `twist_ids` maps every `PermId` to its position-indexed twist coordinate.
+pub fn compare_marginals(
+    iterator: &MtableIterator,
+    pos_chain: &MarginalChain,
+    twist_chain: &MarginalChain,
+    twist_ids: &[u16],
+) -> MarginalComparison {
+    let mut max_abs_diff: f64 = 0.0;
+    let mut total_variation = 0.0;
+    let probs = iterator.get_probs();
+    for (i, (twist_id, prob)) in twist_ids.iter().zip(probs).enumerate() {
+        let pid = i as u32 % PERMID_POSCOUNT;
+        let product = pos_chain.get_prob(pid) * twist_chain.get_prob(*twist_id as u32);
+        let diff = (prob - product).abs();
+        max_abs_diff = max_abs_diff.max(diff);
+        total_variation += diff;
+    }
+    MarginalComparison {
+        full_solved: probs[0],
+        max_abs_diff,
+        total_variation: total_variation / 2.0,
+    }
+}
+
+pub fn generate_twist_ids() -> Vec<u16> {
+    let mut pc = PocketCube::new();
+    (0..PERMID_COUNT as u32)
+        .map(|i| perm_id_to_twist_id(&mut pc, PermId::from_id(i)) as u16)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twist_table_matches_cube() {
+        let table = generate_twist_table();
+        let mut pc = PocketCube::new();
+        let mut tid = 0;
+        for j in [0, 1, 2, 2, 0, 1, 0] {
+            pc.apply(BASE_MOVES[j]);
+            tid = table[tid as usize][j];
+            let mut copy = PocketCube::new();
+            assert_eq!(perm_id_to_twist_id(&mut copy, pc.get_perm_id()), tid);
+        }
+    }
+
+    #[test]
+    fn test_cubic_chain_matches_cube() {
+        let mut pc = PocketCube::new();
+        let mut state = cubic_state_id(CornerPos::FUR, CornerTwist::Rot0);
+        let table = generate_cubic_table();
+        for j in [2, 0, 1, 0, 2, 2] {
+            pc.apply(BASE_MOVES[j]);
+            state = table[state as usize][j];
+            let fur = pc.cubics[0];
+            assert_eq!(state, cubic_state_id(fur.get_pos(), fur.get_rot()));
+        }
+    }
+
+    #[test]
+    fn test_marginal_chains_sum_to_one() {
+        let mut pos_chain = MarginalChain::positions();
+        let mut twist_chain = MarginalChain::twists();
+        pos_chain.set_zero();
+        twist_chain.set_zero();
+        for _ in 0..5 {
+            pos_chain.iterate();
+            twist_chain.iterate();
+        }
+        let pos_sum: f64 = pos_chain.probs.iter().sum();
+        let twist_sum: f64 = twist_chain.probs.iter().sum();
+        assert!((pos_sum - 1.0).abs() < 1e-9);
+        assert!((twist_sum - 1.0).abs() < 1e-9);
+    }
+}
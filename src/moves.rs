@@ -1,7 +1,10 @@
 use crate::enums::CornerPos::*;
 use crate::enums::CornerTwist::*;
-use crate::enums::{CornerPos, CornerTwist};
+use crate::enums::{CornerPos, CornerTwist, CORNER_POSITIONS};
+use std::fmt;
+use std::sync::OnceLock;
 
+#[derive(Clone, Copy)]
 pub struct CubicPerm {
     pub pos: CornerPos,
     pub rot: CornerTwist,
@@ -49,19 +52,109 @@ pub fn move_right(pos: CornerPos) -> CubicPerm {
     }
 }
 
-pub fn move_inv(move_func: impl Fn(CornerPos) -> CubicPerm) -> impl Fn(CornerPos) -> CubicPerm {
-    move |pos: CornerPos| {
-        let perm_1 = move_func(pos);
-        let perm_2 = move_func(perm_1.pos);
-        let perm_3 = move_func(perm_2.pos);
-        CubicPerm::new(
-            perm_3.pos,
-            perm_1.rot.permutate(&perm_2.rot).permutate(&perm_3.rot),
-        )
+pub type MoveFunc = fn(CornerPos) -> CubicPerm;
+
+// Turns of the anchored cube, declared in `MOVES` order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    R,
+    RPrime,
+    R2,
+    U,
+    UPrime,
+    U2,
+    F,
+    FPrime,
+    F2,
+}
+
+pub const MOVES: [Move; 9] = [
+    Move::R,
+    Move::RPrime,
+    Move::R2,
+    Move::U,
+    Move::UPrime,
+    Move::U2,
+    Move::F,
+    Move::FPrime,
+    Move::F2,
+];
+
+// The generators, in the MiniTable column order
+pub const BASE_MOVES: [Move; 3] = [Move::R, Move::U, Move::F];
+
+// Target position and twist of every position, for every move in `MOVES` order
+static MOVE_TABLE: OnceLock<[[CubicPerm; 8]; 9]> = OnceLock::new();
+
+fn get_move_table() -> &'static [[CubicPerm; 8]; 9] {
+    MOVE_TABLE.get_or_init(|| {
+        MOVES.map(|mv| {
+            let move_func: MoveFunc = match mv.get_base() {
+                Move::R => move_right,
+                Move::U => move_up,
+                _ => move_front,
+            };
+            std::array::from_fn(|pos| {
+                let mut perm = CubicPerm::new(CORNER_POSITIONS[pos], Rot0);
+                for _ in 0..mv.quarter_turns() {
+                    let next = move_func(perm.pos);
+                    perm = CubicPerm::new(next.pos, perm.rot.permutate(&next.rot));
+                }
+                perm
+            })
+        })
+    })
+}
+
+impl Move {
+    // `quarter_turns` clockwise quarter turns of R, U or F
+    pub fn from_turns(base: Move, quarter_turns: usize) -> Option<Move> {
+        let index = base.index() / 3;
+        match quarter_turns % 4 {
+            0 => None,
+            1 => Some(MOVES[index * 3]),
+            2 => Some(MOVES[index * 3 + 2]),
+            _ => Some(MOVES[index * 3 + 1]),
+        }
+    }
+
+    // Position in `MOVES`
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    // R, U or F
+    pub fn get_base(self) -> Move {
+        BASE_MOVES[self.index() / 3]
+    }
+
+    pub fn quarter_turns(self) -> usize {
+        match self.index() % 3 {
+            0 => 1,
+            1 => 3,
+            _ => 2,
+        }
+    }
+
+    pub fn inverse(self) -> Move {
+        Move::from_turns(self, 4 - self.quarter_turns()).unwrap()
+    }
+
+    pub fn get_perm(self, pos: CornerPos) -> CubicPerm {
+        get_move_table()[self.index()][u32::from(pos) as usize]
     }
 }
 
-pub type MoveFunc = fn(CornerPos) -> CubicPerm;
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = match self.quarter_turns() {
+            1 => "",
+            2 => "2",
+            _ => "'",
+        };
+        write!(f, "{:?}{}", self.get_base(), suffix)
+    }
+}
 
 // pub fn move_opt_up(pos: CornerPos) -> Option<CubicPerm> {
 //     Some(move_up(pos))
@@ -84,3 +177,50 @@ impl CubicPerm {
         Self { pos, rot }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::PocketCube;
+
+    #[test]
+    fn test_move_table() {
+        let base_funcs: [MoveFunc; 3] = [move_right, move_up, move_front];
+        for (mv, move_func) in BASE_MOVES.into_iter().zip(base_funcs) {
+            for pos in CORNER_POSITIONS {
+                let expected = move_func(pos);
+                let perm = mv.get_perm(pos);
+                assert_eq!(u32::from(perm.pos), u32::from(expected.pos), "{}", mv);
+                assert_eq!(u32::from(perm.rot), u32::from(expected.rot), "{}", mv);
+            }
+        }
+        for mv in MOVES {
+            let mut pc = PocketCube::new();
+            pc.apply(mv);
+            let mut turned = PocketCube::new();
+            for _ in 0..mv.quarter_turns() {
+                turned.apply(mv.get_base());
+            }
+            assert_eq!(
+                pc.to_facelets().unwrap(),
+                turned.to_facelets().unwrap(),
+                "{}",
+                mv
+            );
+
+            pc.apply(mv.inverse());
+            assert_eq!(pc.get_perm_id().get_id(), 0, "{}", mv);
+        }
+    }
+
+    #[test]
+    fn test_move_display() {
+        let names: Vec<String> = MOVES.iter().map(|x| x.to_string()).collect();
+        assert_eq!(names.join(" "), "R R' R2 U U' U2 F F' F2");
+        for (i, mv) in MOVES.into_iter().enumerate() {
+            assert_eq!(mv.index(), i);
+        }
+        assert_eq!(Move::R2.inverse(), Move::R2);
+        assert_eq!(Move::from_turns(Move::UPrime, 4), None);
+    }
+}
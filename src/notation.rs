@@ -0,0 +1,410 @@
+// Singmaster notation
+//
+// Parses move sequences like `R U' F2 L D B' x y2`. Only R, U and F are turns
+// of the anchored cube (see Implementation.md), since the BDL cubic never
+// moves. A turn of the opposite face is the same as the parallel R, U or F turn
+// followed by a whole-cube rotation:
+//
+//     L = R x'    D = U y'    B = F z'
+//
+// so L, D and B turns and x, y and z rotations only change which face of the
+// anchored cube the later turns refer to. That is tracked by `Orientation`.
+
+use crate::group::PocketCube;
+use crate::moves::Move;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    R,
+    L,
+    U,
+    D,
+    F,
+    B,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Amount {
+    Clockwise,
+    Double,
+    CounterClockwise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Face(Face, Amount),
+    Rotation(Axis, Amount),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveSequence {
+    turns: Vec<Turn>,
+}
+
+// Anchored face every face of the held cube currently is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Orientation {
+    faces: [Face; 6],
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    // 1-based, in characters
+    pub column: usize,
+    pub token: String,
+    pub message: String,
+}
+
+const FACES: [Face; 6] = [Face::R, Face::L, Face::U, Face::D, Face::F, Face::B];
+
+impl Face {
+    fn index(self) -> usize {
+        FACES.iter().position(|x| *x == self).unwrap()
+    }
+
+    pub fn opposite(self) -> Face {
+        match self {
+            Face::R => Face::L,
+            Face::L => Face::R,
+            Face::U => Face::D,
+            Face::D => Face::U,
+            Face::F => Face::B,
+            Face::B => Face::F,
+        }
+    }
+
+    // Axis the face turns around, and whether it turns the same way as the
+    // rotation (R, U and F do)
+    pub fn axis(self) -> (Axis, bool) {
+        match self {
+            Face::R => (Axis::X, true),
+            Face::L => (Axis::X, false),
+            Face::U => (Axis::Y, true),
+            Face::D => (Axis::Y, false),
+            Face::F => (Axis::Z, true),
+            Face::B => (Axis::Z, false),
+        }
+    }
+
+    // The anchored move of R, U and F
+    fn to_move(self, amount: Amount) -> Option<Move> {
+        let base = match self {
+            Face::R => Move::R,
+            Face::U => Move::U,
+            Face::F => Move::F,
+            _ => return None,
+        };
+        Move::from_turns(base, amount.quarter_turns())
+    }
+}
+
+impl Axis {
+    // Where a clockwise rotation takes every face
+    fn rotate(self, face: Face) -> Face {
+        let cycle = match self {
+            Axis::X => [Face::F, Face::U, Face::B, Face::D],
+            Axis::Y => [Face::F, Face::L, Face::B, Face::R],
+            Axis::Z => [Face::U, Face::R, Face::D, Face::L],
+        };
+        match cycle.iter().position(|x| *x == face) {
+            Some(i) => cycle[(i + 1) % 4],
+            None => face,
+        }
+    }
+}
+
+impl Amount {
+    pub fn quarter_turns(self) -> usize {
+        match self {
+            Amount::Clockwise => 1,
+            Amount::Double => 2,
+            Amount::CounterClockwise => 3,
+        }
+    }
+
+    pub fn inverse(self) -> Amount {
+        match self {
+            Amount::Clockwise => Amount::CounterClockwise,
+            Amount::Double => Amount::Double,
+            Amount::CounterClockwise => Amount::Clockwise,
+        }
+    }
+
+    // `None` for a multiple of four
+    pub fn from_quarter_turns(quarter_turns: usize) -> Option<Amount> {
+        match quarter_turns % 4 {
+            1 => Some(Amount::Clockwise),
+            2 => Some(Amount::Double),
+            3 => Some(Amount::CounterClockwise),
+            _ => None,
+        }
+    }
+}
+
+impl Orientation {
+    pub fn new() -> Self {
+        Self { faces: FACES }
+    }
+
+    pub fn get_face(&self, face: Face) -> Face {
+        self.faces[face.index()]
+    }
+
+    // The held cube is rotated: the face now at `face` was at the face the
+    // inverse rotation takes it to
+    fn rotate_held(&mut self, axis: Axis, amount: Amount) {
+        let old = self.faces;
+        for face in FACES {
+            let mut from = face;
+            for _ in 0..amount.inverse().quarter_turns() {
+                from = axis.rotate(from);
+            }
+            self.faces[face.index()] = old[from.index()];
+        }
+    }
+
+    // The anchored cube is rotated under the held one
+    fn rotate_anchored(&mut self, axis: Axis, amount: Amount) {
+        for face in self.faces.iter_mut() {
+            for _ in 0..amount.quarter_turns() {
+                *face = axis.rotate(*face);
+            }
+        }
+    }
+}
+
+impl MoveSequence {
+    pub fn new(turns: Vec<Turn>) -> Self {
+        Self { turns }
+    }
+
+    pub fn get_turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut turns = Vec::new();
+        let mut token = String::new();
+        let mut column = 0;
+        for (i, c) in text.chars().chain([' ']).enumerate() {
+            if !c.is_whitespace() {
+                if token.is_empty() {
+                    column = i + 1;
+                }
+                token.push(c);
+            } else if !token.is_empty() {
+                turns.push(Turn::parse(&token, column)?);
+                token.clear();
+            }
+        }
+        Ok(Self::new(turns))
+    }
+
+    // The R, U and F turns of the anchored cube, and the orientation of the held
+    // cube at the end
+    pub fn to_anchored(&self) -> (Vec<Move>, Orientation) {
+        let mut orientation = Orientation::new();
+        let mut anchored = Vec::new();
+        for turn in &self.turns {
+            match *turn {
+                Turn::Face(face, amount) => {
+                    let face = orientation.get_face(face);
+                    let (axis, positive) = face.axis();
+                    if positive {
+                        anchored.push(face.to_move(amount).unwrap());
+                    } else {
+                        // e.g. L = R x'
+                        anchored.push(face.opposite().to_move(amount).unwrap());
+                        orientation.rotate_anchored(axis, amount);
+                    }
+                }
+                Turn::Rotation(axis, amount) => orientation.rotate_held(axis, amount),
+            }
+        }
+        (anchored, orientation)
+    }
+
+    pub fn apply(&self, pc: &mut PocketCube) {
+        for mv in self.to_anchored().0 {
+            pc.apply(mv);
+        }
+    }
+}
+
+impl Turn {
+    pub fn inverse(self) -> Turn {
+        match self {
+            Turn::Face(face, amount) => Turn::Face(face, amount.inverse()),
+            Turn::Rotation(axis, amount) => Turn::Rotation(axis, amount.inverse()),
+        }
+    }
+
+    // Mirrored across the plane between R and L, which swaps R and L and turns
+    // everything else the other way, except x rotations
+    pub fn mirror(self) -> Turn {
+        match self {
+            Turn::Face(face @ (Face::R | Face::L), amount) => {
+                Turn::Face(face.opposite(), amount.inverse())
+            }
+            Turn::Face(face, amount) => Turn::Face(face, amount.inverse()),
+            Turn::Rotation(Axis::X, amount) => Turn::Rotation(Axis::X, amount),
+            Turn::Rotation(axis, amount) => Turn::Rotation(axis, amount.inverse()),
+        }
+    }
+
+    pub fn get_amount(self) -> Amount {
+        match self {
+            Turn::Face(_, amount) | Turn::Rotation(_, amount) => amount,
+        }
+    }
+
+    // The same face or axis turned by `amount`
+    pub fn with_amount(self, amount: Amount) -> Turn {
+        match self {
+            Turn::Face(face, _) => Turn::Face(face, amount),
+            Turn::Rotation(axis, _) => Turn::Rotation(axis, amount),
+        }
+    }
+
+    pub fn parse(token: &str, column: usize) -> Result<Self, ParseError> {
+        let err = |message: &str| ParseError {
+            column,
+            token: token.to_string(),
+            message: message.to_string(),
+        };
+        let mut chars = token.chars();
+        let kind = match chars.next().unwrap() {
+            'R' => Turn::Face(Face::R, Amount::Clockwise),
+            'L' => Turn::Face(Face::L, Amount::Clockwise),
+            'U' => Turn::Face(Face::U, Amount::Clockwise),
+            'D' => Turn::Face(Face::D, Amount::Clockwise),
+            'F' => Turn::Face(Face::F, Amount::Clockwise),
+            'B' => Turn::Face(Face::B, Amount::Clockwise),
+            'x' => Turn::Rotation(Axis::X, Amount::Clockwise),
+            'y' => Turn::Rotation(Axis::Y, Amount::Clockwise),
+            'z' => Turn::Rotation(Axis::Z, Amount::Clockwise),
+            'r' | 'l' | 'u' | 'd' | 'f' | 'b' | 'M' | 'E' | 'S' => {
+                return Err(err("Wide and slice moves do not exist on the pocket cube"))
+            }
+            _ => return Err(err("Unknown move")),
+        };
+        // `2'` is sometimes written for a double turn as well
+        let amount = match chars.as_str() {
+            "" => Amount::Clockwise,
+            "'" => Amount::CounterClockwise,
+            "2" | "2'" => Amount::Double,
+            _ => return Err(err("Invalid suffix of move")),
+        };
+        Ok(kind.with_amount(amount))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Amount::Clockwise => Ok(()),
+            Amount::Double => write!(f, "2"),
+            Amount::CounterClockwise => write!(f, "'"),
+        }
+    }
+}
+
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Turn::Face(face, amount) => write!(f, "{:?}{}", face, amount),
+            Turn::Rotation(axis, amount) => {
+                write!(f, "{}{}", format!("{:?}", axis).to_lowercase(), amount)
+            }
+        }
+    }
+}
+
+impl fmt::Display for MoveSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let turns: Vec<String> = self.turns.iter().map(|x| x.to_string()).collect();
+        write!(f, "{}", turns.join(" "))
+    }
+}
+
+impl ParseError {
+    // Marks the token under the parsed text
+    pub fn pointer(&self) -> String {
+        format!(
+            "{}{}",
+            " ".repeat(self.column - 1),
+            "^".repeat(self.token.chars().count())
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} '{}' at column {}",
+            self.message, self.token, self.column
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perm_id(text: &str) -> u32 {
+        let mut pc = PocketCube::new();
+        MoveSequence::parse(text).unwrap().apply(&mut pc);
+        pc.get_perm_id().get_id()
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let text = "R U' F2 L D B' x y2 z'";
+        assert_eq!(MoveSequence::parse(text).unwrap().to_string(), text);
+        assert_eq!(
+            MoveSequence::parse("  R2'\tU ").unwrap(),
+            MoveSequence::new(vec![
+                Turn::Face(Face::R, Amount::Double),
+                Turn::Face(Face::U, Amount::Clockwise)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = MoveSequence::parse("R U' Q2 F").unwrap_err();
+        assert_eq!(error.column, 6);
+        assert_eq!(error.token, "Q2");
+        assert_eq!(MoveSequence::parse("R3").unwrap_err().column, 1);
+        assert_eq!(MoveSequence::parse("R r").unwrap_err().token, "r");
+    }
+
+    #[test]
+    fn test_rotations() {
+        assert_eq!(perm_id("L R'"), 0);
+        assert_eq!(perm_id("x y' z2"), 0);
+        assert_eq!(perm_id("y R y'"), perm_id("B"));
+        assert_eq!(perm_id("x U x'"), perm_id("F"));
+        assert_eq!(perm_id("D"), perm_id("U"));
+        assert_ne!(perm_id("L U"), perm_id("R U"));
+    }
+
+    #[test]
+    fn test_opposite_face_commutators() {
+        // sexy moves have order 6 on any pair of adjacent faces
+        for pair in ["L U L' U'", "D F D' F'", "B L B' L'", "L' D' L D"] {
+            let text = [pair; 6].join(" ");
+            assert_eq!(perm_id(&text), 0, "{}", pair);
+            assert_ne!(perm_id(pair), 0, "{}", pair);
+        }
+    }
+}
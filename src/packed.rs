@@ -0,0 +1,235 @@
+// Packed 24-bit MiniTable
+//
+// Every `PermId` fits into 24 bits, so a MiniTable row can be stored in 9 bytes
+// instead of 16 (see Notes.md). This saves 44% of the memory, but the rows are
+// no longer aligned, so whether it is also faster depends on the machine; `auto`
+// benchmarks both formats on a sample of rows and keeps the faster one.
+
+use crate::calc::{
+    decode_row, read_mini_table, write_to_file, BranchTable, MiniTable, MINI_TABLE_MOVES,
+    MINI_TABLE_ROW_WIDTH,
+};
+use crate::table::{open_table_header, Checksum, TableError, TableHeader};
+use crate::types::PERMID_COUNT;
+use clap::ValueEnum;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{BufWriter, Read, Write};
+use std::time::{Duration, Instant};
+
+pub const MINI_TABLE_PACKED_ROW_WIDTH: u32 = 9;
+
+const BENCH_SAMPLES: u32 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum TableFormat {
+    Padded,
+    Packed,
+}
+
+pub struct PackedMiniTable {
+    data: Vec<u8>,
+}
+
+// A MiniTable in either format, as loaded from a file
+pub enum AnyMiniTable {
+    Padded(MiniTable),
+    Packed(PackedMiniTable),
+}
+
+impl PackedMiniTable {
+    pub fn from_mini_table(mtable: &MiniTable) -> Self {
+        let mut data = Vec::with_capacity(mtable.len() * MINI_TABLE_PACKED_ROW_WIDTH as usize);
+        for row in mtable {
+            for x in &row[..3] {
+                data.extend_from_slice(&x.to_le_bytes()[..3]);
+            }
+        }
+        Self { data }
+    }
+
+    pub fn to_mini_table(&self) -> MiniTable {
+        (0..self.len() as u32)
+            .map(|id| {
+                let branches = self.get_branches(id);
+                [branches[0], branches[1], branches[2], 0]
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / MINI_TABLE_PACKED_ROW_WIDTH as usize
+    }
+}
+
+impl BranchTable for PackedMiniTable {
+    fn get_branches(&self, id: u32) -> [u32; 3] {
+        let start = id as usize * MINI_TABLE_PACKED_ROW_WIDTH as usize;
+        let row = &self.data[start..start + MINI_TABLE_PACKED_ROW_WIDTH as usize];
+        let mut branches = [0; 3];
+        for j in 0..3 {
+            branches[j] =
+                row[3 * j] as u32 | (row[3 * j + 1] as u32) << 8 | (row[3 * j + 2] as u32) << 16;
+        }
+        branches
+    }
+}
+
+pub fn write_packed_to_file(table: &PackedMiniTable, fname: &str) -> Result<(), TableError> {
+    let mut checksum = Checksum::new();
+    checksum.update(&table.data);
+    let header = TableHeader::new(
+        MINI_TABLE_MOVES,
+        MINI_TABLE_PACKED_ROW_WIDTH,
+        table.len() as u32,
+        checksum.get(),
+    );
+
+    let mut f = BufWriter::new(File::create(fname)?);
+    header.write(&mut f)?;
+    f.write_all(&table.data)?;
+    f.flush()?;
+    Ok(())
+}
+
+// Reads the rows following an already read header, which is validated first
+pub fn read_packed_table(
+    reader: &mut impl Read,
+    header: &TableHeader,
+) -> Result<PackedMiniTable, TableError> {
+    header.validate(
+        MINI_TABLE_MOVES,
+        MINI_TABLE_PACKED_ROW_WIDTH,
+        PERMID_COUNT as u32,
+    )?;
+    let mut data = vec![0; header.data_size() as usize];
+    reader.read_exact(&mut data)?;
+    let mut checksum = Checksum::new();
+    checksum.update(&data);
+    header.validate_checksum(&checksum)?;
+    Ok(PackedMiniTable { data })
+}
+
+impl AnyMiniTable {
+    // Loads a MiniTable file in whichever format it was written
+    pub fn load(fname: &str) -> Result<Self, TableError> {
+        let (mut reader, header) = open_table_header(fname)?;
+        if header.row_width == MINI_TABLE_PACKED_ROW_WIDTH {
+            Ok(AnyMiniTable::Packed(read_packed_table(
+                &mut reader,
+                &header,
+            )?))
+        } else {
+            Ok(AnyMiniTable::Padded(read_mini_table(&mut reader, &header)?))
+        }
+    }
+
+    // Loads a MiniTable file without failing on a checksum mismatch, which is
+    // returned separately so that `verify` can still locate the damaged rows
+    pub fn load_unchecked(fname: &str) -> Result<(Self, Result<(), TableError>), TableError> {
+        let (mut reader, header) = open_table_header(fname)?;
+        let row_width = if header.row_width == MINI_TABLE_PACKED_ROW_WIDTH {
+            MINI_TABLE_PACKED_ROW_WIDTH
+        } else {
+            MINI_TABLE_ROW_WIDTH
+        };
+        header.validate(MINI_TABLE_MOVES, row_width, PERMID_COUNT as u32)?;
+        let mut data = vec![0; header.data_size() as usize];
+        reader.read_exact(&mut data)?;
+        let mut checksum = Checksum::new();
+        checksum.update(&data);
+
+        let table = if row_width == MINI_TABLE_PACKED_ROW_WIDTH {
+            AnyMiniTable::Packed(PackedMiniTable { data })
+        } else {
+            AnyMiniTable::Padded(
+                data.chunks_exact(MINI_TABLE_ROW_WIDTH as usize)
+                    .map(|x| decode_row(x.try_into().unwrap(), header.endianness))
+                    .collect(),
+            )
+        };
+        Ok((table, header.validate_checksum(&checksum)))
+    }
+
+    pub fn get_format(&self) -> TableFormat {
+        match self {
+            AnyMiniTable::Padded(_) => TableFormat::Padded,
+            AnyMiniTable::Packed(_) => TableFormat::Packed,
+        }
+    }
+
+    pub fn into_format(self, format: TableFormat) -> Self {
+        match (self, format) {
+            (AnyMiniTable::Padded(mtable), TableFormat::Packed) => {
+                AnyMiniTable::Packed(PackedMiniTable::from_mini_table(&mtable))
+            }
+            (AnyMiniTable::Packed(table), TableFormat::Padded) => {
+                AnyMiniTable::Padded(table.to_mini_table())
+            }
+            (table, _) => table,
+        }
+    }
+
+    // Converts to both formats, benchmarks them and keeps the faster one
+    pub fn into_fastest(self) -> Self {
+        let (padded, packed) = match self {
+            AnyMiniTable::Padded(mtable) => {
+                let packed = PackedMiniTable::from_mini_table(&mtable);
+                (mtable, packed)
+            }
+            AnyMiniTable::Packed(table) => (table.to_mini_table(), table),
+        };
+        let padded_time = bench_branch_table(&padded);
+        let packed_time = bench_branch_table(&packed);
+        println!(
+            "Benchmark: padded {:?}, packed {:?}",
+            padded_time, packed_time
+        );
+        if packed_time < padded_time {
+            AnyMiniTable::Packed(packed)
+        } else {
+            AnyMiniTable::Padded(padded)
+        }
+    }
+
+    pub fn write_to_file(&self, fname: &str) -> Result<(), TableError> {
+        match self {
+            AnyMiniTable::Padded(mtable) => write_to_file(mtable, fname),
+            AnyMiniTable::Packed(table) => write_packed_to_file(table, fname),
+        }
+    }
+
+    pub fn into_branch_table(self) -> Box<dyn BranchTable> {
+        match self {
+            AnyMiniTable::Padded(mtable) => Box::new(mtable),
+            AnyMiniTable::Packed(table) => Box::new(table),
+        }
+    }
+}
+
+// Time to look up the rows of a sample of states, in the scattered order of an
+// `MtableIterator` iteration
+pub fn bench_branch_table(table: &dyn BranchTable) -> Duration {
+    let start = Instant::now();
+    let mut id: u32 = 0;
+    for _ in 0..BENCH_SAMPLES {
+        let branches = black_box(table.get_branches(id));
+        id = branches[(id % 3) as usize];
+    }
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_round_trip() {
+        let mtable: MiniTable = vec![[0, 1, 3674159, 0], [16777215, 256, 65536, 0]];
+        let packed = PackedMiniTable::from_mini_table(&mtable);
+        assert_eq!(packed.len(), 2);
+        assert_eq!(packed.get_branches(0), [0, 1, 3674159]);
+        assert_eq!(packed.get_branches(1), [16777215, 256, 65536]);
+        assert_eq!(packed.to_mini_table(), mtable);
+    }
+}
@@ -98,7 +98,7 @@ pub fn smushed_to_array<T, const N: usize>(id: u32, elm_func: impl Fn(u32) -> T)
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use crate::group::PocketCube;
 
     #[test]
     fn test_identity_pos() {
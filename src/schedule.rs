@@ -0,0 +1,194 @@
+// Time-inhomogeneous move schedules for `MtableIterator`
+//
+// A schedule file has one entry per line: a step range followed by the moves
+// allowed during those steps, each with an optional weight (default 1).
+// Weights are normalized per entry. Empty lines and `#` comments are ignored.
+//
+//     # steps  moves
+//     0..10    R U
+//     10..     R U F:2
+//
+// Step ranges are `a..b` (b excluded), `a..` (open ended) or a single step `a`.
+
+use std::fmt;
+use std::fs;
+
+// Same move order as the MiniTable columns
+pub const SCHEDULE_MOVES: [&str; 3] = ["R", "U", "F"];
+
+#[derive(Debug, Clone)]
+pub struct ScheduleEntry {
+    start: usize,
+    end: Option<usize>,
+    weights: [f64; 3],
+}
+
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    entries: Vec<ScheduleEntry>,
+}
+
+#[derive(Debug)]
+pub struct ScheduleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ScheduleEntry {
+    pub fn new(start: usize, end: Option<usize>, weights: [f64; 3]) -> Self {
+        let sum: f64 = weights.iter().sum();
+        Self {
+            start,
+            end,
+            weights: weights.map(|x| x / sum),
+        }
+    }
+
+    pub fn contains(&self, step: usize) -> bool {
+        step >= self.start && self.end.is_none_or(|end| step < end)
+    }
+
+    pub fn get_weights(&self) -> [f64; 3] {
+        self.weights
+    }
+
+    fn parse(line: &str, line_no: usize) -> Result<Self, ScheduleError> {
+        let err = |message: String| ScheduleError {
+            line: line_no,
+            message,
+        };
+        let mut tokens = line.split_whitespace();
+        let range = tokens.next().ok_or(err("Missing step range".to_string()))?;
+        let parse_step = |x: &str| {
+            x.parse::<usize>()
+                .map_err(|_| err(format!("Invalid step '{}'", x)))
+        };
+        let (start, end) = match range.split_once("..") {
+            Some((start, "")) => (parse_step(start)?, None),
+            Some((start, end)) => (parse_step(start)?, Some(parse_step(end)?)),
+            None => (parse_step(range)?, Some(parse_step(range)? + 1)),
+        };
+        if end.is_some_and(|end| end <= start) {
+            return Err(err(format!("Empty step range '{}'", range)));
+        }
+
+        let mut weights = [0.0; 3];
+        for token in tokens {
+            let (name, weight) = match token.split_once(':') {
+                Some((name, weight)) => (
+                    name,
+                    weight
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|x| x.is_finite() && *x >= 0.0)
+                        .ok_or(err(format!("Invalid weight '{}'", weight)))?,
+                ),
+                None => (token, 1.0),
+            };
+            let index = SCHEDULE_MOVES
+                .iter()
+                .position(|x| *x == name)
+                .ok_or(err(format!("Unknown move '{}'", name)))?;
+            weights[index] += weight;
+        }
+        if weights.iter().sum::<f64>() <= 0.0 {
+            return Err(err("No moves with positive weight".to_string()));
+        }
+
+        Ok(Self::new(start, end, weights))
+    }
+}
+
+impl Schedule {
+    pub fn new(entries: Vec<ScheduleEntry>) -> Self {
+        Self { entries }
+    }
+
+    // Every step uses R, U and F with equal weights
+    pub fn uniform() -> Self {
+        Self::new(vec![ScheduleEntry::new(0, None, [1.0; 3])])
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScheduleError> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if !line.is_empty() {
+                entries.push(ScheduleEntry::parse(line, i + 1)?);
+            }
+        }
+        Ok(Self::new(entries))
+    }
+
+    pub fn load(fname: &str) -> Result<Self, ScheduleError> {
+        let text = fs::read_to_string(fname).map_err(|e| ScheduleError {
+            line: 0,
+            message: format!("Unable to read schedule file '{}': {}", fname, e),
+        })?;
+        Self::parse(&text)
+    }
+
+    // The first entry containing the step wins
+    pub fn get_entry(&self, step: usize) -> Option<&ScheduleEntry> {
+        self.entries.iter().find(|x| x.contains(step))
+    }
+}
+
+impl fmt::Display for ScheduleEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}..{}", self.start, end)?,
+            None => write!(f, "{}..", self.start)?,
+        }
+        for (name, weight) in SCHEDULE_MOVES.iter().zip(self.weights.iter()) {
+            if *weight > 0.0 {
+                write!(f, " {}:{}", name, weight)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Schedule line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schedule() {
+        let schedule = Schedule::parse("# comment\n0..10 R U\n10.. R U F:2\n").unwrap();
+        assert_eq!(
+            schedule.get_entry(3).unwrap().get_weights(),
+            [0.5, 0.5, 0.0]
+        );
+        assert_eq!(
+            schedule.get_entry(10).unwrap().get_weights(),
+            [0.25, 0.25, 0.5]
+        );
+        assert_eq!(
+            schedule.get_entry(10).unwrap().to_string(),
+            "10.. R:0.25 U:0.25 F:0.5"
+        );
+    }
+
+    #[test]
+    fn test_parse_schedule_errors() {
+        assert_eq!(Schedule::parse("0..5 R\n5..3 U").unwrap_err().line, 2);
+        assert_eq!(Schedule::parse("0 X").unwrap_err().line, 1);
+        assert!(Schedule::parse("2").is_err());
+        assert!(Schedule::parse("0..2 R:-1").is_err());
+    }
+
+    #[test]
+    fn test_uncovered_step() {
+        let schedule = Schedule::parse("2 R").unwrap();
+        assert!(schedule.get_entry(1).is_none());
+        assert!(schedule.get_entry(2).is_some());
+        assert!(schedule.get_entry(3).is_none());
+    }
+}
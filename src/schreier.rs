@@ -0,0 +1,623 @@
+// Schreier–Sims over facelet permutations
+//
+// A state of the whole cube, including the heart piece and the orientation it
+// is held in, is a permutation of the 24 facelets of `to_facelets`: `images[i]`
+// is the facelet the sticker at facelet `i` moves to. Unlike `PermId`s this
+// covers L, D and B turns and cube rotations, so any set of algorithms
+// generates a permutation group of the facelets. Products are read like move
+// sequences, `a * b` is `a` followed by `b`.
+//
+// `StabilizerChain` is the deterministic Schreier–Sims algorithm: for a base
+// b0, b1, ... every level i stores a transversal of the orbit of bi under the
+// stabilizer of b0 .. bi-1, and the strong generators are added until every
+// Schreier generator sifts through the levels below. The order of the group
+// is the product of the orbit lengths.
+//
+// Every transversal element and strong generator also keeps a `Word` in the
+// original generators, so sifting a member down to the identity writes it as a
+// product of the generators.
+//
+// Any permutation of the facelets can be a generator, not only cube states: it
+// is built from its images with `TryFrom<[u8; 24]>` or parsed from the cycle
+// notation that `Display` writes, e.g. `(0 1 3 2) (4 16 12 8)`.
+
+use crate::algorithm::Algorithm;
+use crate::enums::CubeRealSide;
+use crate::facelets::{X_PERM, Y_PERM};
+use crate::group::{PocketCube, CORNER_FACELETS, SOLVED_FACELETS};
+use crate::moves::Move;
+use crate::notation::{Axis, Turn};
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::Mul;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaceletPerm {
+    images: [u8; 24],
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PermError {
+    // a facelet index of 24 or more
+    Facelet(usize),
+    // a facelet that is the image of two facelets, or appears in two cycles
+    Repeated(usize),
+    // a character that is not a digit, space or parenthesis, or an unclosed
+    // cycle
+    Syntax(usize),
+}
+
+// A product of the generators, as the index and exponent of every factor
+pub type Word = Vec<(usize, i64)>;
+
+struct Level {
+    base_point: usize,
+    // element of the group taking the base point to every point of its orbit
+    transversal: Vec<Option<(FaceletPerm, Word)>>,
+}
+
+pub struct StabilizerChain {
+    // order of every generator, to keep exponents small
+    generator_orders: Vec<usize>,
+    strong_generators: Vec<(FaceletPerm, Word)>,
+    levels: Vec<Level>,
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl FaceletPerm {
+    pub fn identity() -> Self {
+        Self {
+            images: std::array::from_fn(|i| i as u8),
+        }
+    }
+
+    // From `rotated[i] = facelets[perm[i]]`, as the rotations of facelets.rs
+    fn from_gather(perm: &[usize; 24]) -> Self {
+        let mut images = [0; 24];
+        for (i, from) in perm.iter().enumerate() {
+            images[*from] = i as u8;
+        }
+        Self { images }
+    }
+
+    // Side `j` of a cubic that starts at a position is at facelet
+    // `CORNER_FACELETS[pos][j]`, and ends up as side `j + rot` of its position
+    pub fn from_pocket_cube(pc: &PocketCube) -> Self {
+        let mut images = [0; 24];
+        for (start, cubic) in CORNER_FACELETS.iter().zip(pc.cubics) {
+            let end = CORNER_FACELETS[u32::from(cubic.get_pos()) as usize];
+            let rot = u32::from(cubic.get_rot()) as usize;
+            for j in 0..3 {
+                images[start[j]] = end[(j + rot) % 3] as u8;
+            }
+        }
+        Self { images }
+    }
+
+    // Clockwise whole-cube rotation
+    pub fn from_rotation(axis: Axis) -> Self {
+        let x = Self::from_gather(&X_PERM);
+        let y = Self::from_gather(&Y_PERM);
+        match axis {
+            Axis::X => x,
+            Axis::Y => y,
+            Axis::Z => x.inverse() * y.inverse() * x,
+        }
+    }
+
+    pub fn from_move(mv: Move) -> Self {
+        let mut pc = PocketCube::new();
+        pc.apply(mv);
+        Self::from_pocket_cube(&pc)
+    }
+
+    pub fn from_turn(turn: Turn) -> Self {
+        let quarter_turn = match turn {
+            Turn::Face(face, _) => {
+                let (axis, positive) = face.axis();
+                let base = match axis {
+                    Axis::X => Move::R,
+                    Axis::Y => Move::U,
+                    Axis::Z => Move::F,
+                };
+                // e.g. L = R x'
+                if positive {
+                    Self::from_move(base)
+                } else {
+                    Self::from_move(base) * Self::from_rotation(axis).inverse()
+                }
+            }
+            Turn::Rotation(axis, _) => Self::from_rotation(axis),
+        };
+        quarter_turn.pow(turn.get_amount().quarter_turns())
+    }
+
+    pub fn from_algorithm(algorithm: &Algorithm) -> Self {
+        algorithm
+            .to_sequence()
+            .get_turns()
+            .iter()
+            .fold(Self::identity(), |perm, turn| perm * Self::from_turn(*turn))
+    }
+
+    // Cycles of facelet indexes like `(0 1 3 2) (4 16 12 8)`, `()` for the
+    // identity. Commas may separate the indexes as well.
+    pub fn parse(text: &str) -> Result<Self, PermError> {
+        let mut images: [u8; 24] = std::array::from_fn(|i| i as u8);
+        let mut seen = [false; 24];
+        let mut cycle: Option<Vec<usize>> = None;
+        let mut number = String::new();
+        for (i, c) in text.chars().chain([' ']).enumerate() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            if !number.is_empty() {
+                let point: usize = number.parse().map_err(|_| PermError::Syntax(i))?;
+                number.clear();
+                let Some(points) = cycle.as_mut() else {
+                    return Err(PermError::Syntax(i));
+                };
+                if point >= 24 {
+                    return Err(PermError::Facelet(point));
+                }
+                if seen[point] {
+                    return Err(PermError::Repeated(point));
+                }
+                seen[point] = true;
+                points.push(point);
+            }
+            match c {
+                '(' if cycle.is_none() => cycle = Some(Vec::new()),
+                ')' => {
+                    let points = cycle.take().ok_or(PermError::Syntax(i))?;
+                    for (j, point) in points.iter().enumerate() {
+                        images[*point] = points[(j + 1) % points.len()] as u8;
+                    }
+                }
+                ',' if cycle.is_some() => {}
+                _ if c.is_whitespace() => {}
+                _ => return Err(PermError::Syntax(i)),
+            }
+        }
+        if cycle.is_some() {
+            return Err(PermError::Syntax(text.chars().count()));
+        }
+        Ok(Self { images })
+    }
+
+    pub fn apply(&self, point: usize) -> usize {
+        self.images[point] as usize
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut images = [0; 24];
+        for (i, image) in self.images.iter().enumerate() {
+            images[*image as usize] = i as u8;
+        }
+        Self { images }
+    }
+
+    pub fn pow(&self, exponent: usize) -> Self {
+        (0..exponent).fold(Self::identity(), |perm, _| perm * *self)
+    }
+
+    pub fn order(&self) -> usize {
+        let mut order = 1;
+        for length in self.get_cycles().iter().map(|x| x.len()) {
+            order = order / gcd(order, length) * length;
+        }
+        order
+    }
+
+    // Cycles of length at least 2, each starting at its lowest facelet
+    pub fn get_cycles(&self) -> Vec<Vec<usize>> {
+        let mut visited = [false; 24];
+        let mut cycles = Vec::new();
+        for start in 0..24 {
+            let mut cycle = Vec::new();
+            let mut i = start;
+            while !visited[i] {
+                visited[i] = true;
+                cycle.push(i);
+                i = self.apply(i);
+            }
+            if cycle.len() > 1 {
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    // The facelets of the solved cube after the permutation
+    pub fn to_facelets(self) -> [CubeRealSide; 24] {
+        let mut facelets = SOLVED_FACELETS;
+        for (i, side) in SOLVED_FACELETS.iter().enumerate() {
+            facelets[self.apply(i)] = *side;
+        }
+        facelets
+    }
+}
+
+impl Mul for FaceletPerm {
+    type Output = FaceletPerm;
+
+    fn mul(self, rhs: FaceletPerm) -> FaceletPerm {
+        Self {
+            images: self.images.map(|x| rhs.images[x as usize]),
+        }
+    }
+}
+
+impl TryFrom<[u8; 24]> for FaceletPerm {
+    type Error = PermError;
+
+    // `images[i]` is the facelet that facelet `i` moves to
+    fn try_from(images: [u8; 24]) -> Result<Self, Self::Error> {
+        let mut seen = [false; 24];
+        for image in images {
+            let image = image as usize;
+            if image >= 24 {
+                return Err(PermError::Facelet(image));
+            }
+            if seen[image] {
+                return Err(PermError::Repeated(image));
+            }
+            seen[image] = true;
+        }
+        Ok(Self { images })
+    }
+}
+
+impl fmt::Display for PermError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PermError::Facelet(index) => {
+                write!(f, "Invalid facelet {}, expected 0 to 23", index)
+            }
+            PermError::Repeated(index) => write!(f, "Facelet {} appears more than once", index),
+            PermError::Syntax(column) => {
+                write!(f, "Invalid cycle notation at character {}", column + 1)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FaceletPerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cycles: Vec<String> = self
+            .get_cycles()
+            .iter()
+            .map(|cycle| {
+                let points: Vec<String> = cycle.iter().map(|x| x.to_string()).collect();
+                format!("({})", points.join(" "))
+            })
+            .collect();
+        if cycles.is_empty() {
+            write!(f, "()")
+        } else {
+            write!(f, "{}", cycles.join(" "))
+        }
+    }
+}
+
+impl StabilizerChain {
+    pub fn new(generators: &[FaceletPerm]) -> Self {
+        let mut chain = Self {
+            generator_orders: generators.iter().map(|x| x.order()).collect(),
+            strong_generators: Vec::new(),
+            levels: Vec::new(),
+        };
+        for (i, generator) in generators.iter().enumerate() {
+            if !generator.is_identity() {
+                chain.add_strong_generator(*generator, vec![(i, 1)]);
+            }
+        }
+        chain.complete();
+        chain
+    }
+
+    pub fn order(&self) -> u128 {
+        self.get_orbit_lengths()
+            .iter()
+            .map(|x| *x as u128)
+            .product()
+    }
+
+    pub fn get_base(&self) -> Vec<usize> {
+        self.levels.iter().map(|x| x.base_point).collect()
+    }
+
+    pub fn get_orbit_lengths(&self) -> Vec<usize> {
+        self.levels
+            .iter()
+            .map(|x| x.transversal.iter().flatten().count())
+            .collect()
+    }
+
+    pub fn get_strong_generators(&self) -> &[(FaceletPerm, Word)] {
+        &self.strong_generators
+    }
+
+    pub fn contains(&self, perm: &FaceletPerm) -> bool {
+        self.factorize(perm).is_some()
+    }
+
+    // `perm` as a product of the generators, `None` if it is not in the group
+    pub fn factorize(&self, perm: &FaceletPerm) -> Option<Word> {
+        let (residue, _, level, factors) = self.sift(*perm, Vec::new(), 0);
+        if level < self.levels.len() || !residue.is_identity() {
+            return None;
+        }
+        // perm = u_k ... u_1 for the transversal elements u_1 .. u_k divided out
+        let mut word = Vec::new();
+        for factor in factors.iter().rev() {
+            self.append(&mut word, factor);
+        }
+        Some(word)
+    }
+
+    // Appends `other` to `word`, cancelling and merging factors at the seam
+    fn append(&self, word: &mut Word, other: &Word) {
+        for (i, exponent) in other {
+            let order = self.generator_orders[*i] as i64;
+            let mut exponent = *exponent;
+            if let Some((last, last_exponent)) = word.last() {
+                if last == i {
+                    exponent += last_exponent;
+                    word.pop();
+                }
+            }
+            // the exponent closest to 0
+            exponent = exponent.rem_euclid(order);
+            if exponent > order / 2 {
+                exponent -= order;
+            }
+            if exponent != 0 {
+                word.push((*i, exponent));
+            }
+        }
+    }
+
+    fn invert(&self, word: &Word) -> Word {
+        word.iter().rev().map(|(i, e)| (*i, -e)).collect()
+    }
+
+    // Divides `perm` by the transversal elements of the levels from `level` on
+    // until its image of a base point is outside the orbit. Returns the residue,
+    // its word, the level it stopped at (the number of levels if it sifted
+    // through) and the words of the transversal elements divided out.
+    fn sift(
+        &self,
+        mut perm: FaceletPerm,
+        mut word: Word,
+        level: usize,
+    ) -> (FaceletPerm, Word, usize, Vec<Word>) {
+        let mut factors = Vec::new();
+        for (i, current) in self.levels.iter().enumerate().skip(level) {
+            match &current.transversal[perm.apply(current.base_point)] {
+                Some((u, u_word)) => {
+                    perm = perm * u.inverse();
+                    self.append(&mut word, &self.invert(u_word));
+                    factors.push(u_word.clone());
+                }
+                None => return (perm, word, i, factors),
+            }
+        }
+        (perm, word, self.levels.len(), factors)
+    }
+
+    // Strong generators that fix the base points before `level`
+    fn get_level_generators(&self, level: usize) -> Vec<&(FaceletPerm, Word)> {
+        let base = self.get_base();
+        self.strong_generators
+            .iter()
+            .filter(|(perm, _)| base[..level].iter().all(|b| perm.apply(*b) == *b))
+            .collect()
+    }
+
+    // Search of the orbit of the base point that keeps the element with the
+    // shortest word for every point, so factorizations stay short
+    fn build_transversal(&mut self, level: usize) {
+        let base_point = self.levels[level].base_point;
+        let mut transversal: Vec<Option<(FaceletPerm, Word)>> = vec![None; 24];
+        transversal[base_point] = Some((FaceletPerm::identity(), Vec::new()));
+        let mut queue = VecDeque::from([base_point]);
+        let generators: Vec<(FaceletPerm, Word)> = self
+            .get_level_generators(level)
+            .into_iter()
+            .cloned()
+            .collect();
+        while let Some(point) = queue.pop_front() {
+            let (u, u_word) = transversal[point].clone().unwrap();
+            for (s, s_word) in &generators {
+                let image = s.apply(point);
+                let mut word = u_word.clone();
+                self.append(&mut word, s_word);
+                let shorter = match &transversal[image] {
+                    Some((_, old)) => word.len() < old.len(),
+                    None => true,
+                };
+                if shorter {
+                    transversal[image] = Some((u * *s, word));
+                    queue.push_back(image);
+                }
+            }
+        }
+        self.levels[level].transversal = transversal;
+    }
+
+    // Adds a generator that fixes every base point before `level`, or every base
+    // point if `level` is the number of levels, and returns `level`
+    fn add_strong_generator(&mut self, perm: FaceletPerm, word: Word) -> usize {
+        let level = self
+            .levels
+            .iter()
+            .position(|x| perm.apply(x.base_point) != x.base_point)
+            .unwrap_or(self.levels.len());
+        if level == self.levels.len() {
+            let base_point = (0..24).find(|x| perm.apply(*x) != *x).unwrap();
+            self.levels.push(Level {
+                base_point,
+                transversal: Vec::new(),
+            });
+        }
+        self.strong_generators.push((perm, word));
+        for i in 0..=level {
+            self.build_transversal(i);
+        }
+        level
+    }
+
+    // Checks the levels from the last one up. A Schreier generator that does not
+    // sift through the levels below is added, and the check restarts at the
+    // level it was added to.
+    fn complete(&mut self) {
+        let mut level = self.levels.len();
+        while level > 0 {
+            let current = level - 1;
+            match self.find_missing_generator(current) {
+                Some((residue, word)) => level = self.add_strong_generator(residue, word) + 1,
+                None => level = current,
+            }
+        }
+    }
+
+    fn find_missing_generator(&self, level: usize) -> Option<(FaceletPerm, Word)> {
+        let transversal = &self.levels[level].transversal;
+        for (u, u_word) in transversal.iter().flatten() {
+            for (s, s_word) in self.get_level_generators(level) {
+                let (v, v_word) = transversal[s.apply(u.apply(self.levels[level].base_point))]
+                    .as_ref()
+                    .unwrap();
+                let schreier = *u * *s * v.inverse();
+                if schreier.is_identity() {
+                    continue;
+                }
+                let mut word = u_word.clone();
+                self.append(&mut word, s_word);
+                self.append(&mut word, &self.invert(v_word));
+                let (residue, word, _, _) = self.sift(schreier, word, level + 1);
+                if !residue.is_identity() {
+                    return Some((residue, word));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PermId;
+
+    fn perm(text: &str) -> FaceletPerm {
+        FaceletPerm::from_algorithm(&Algorithm::parse(text).unwrap())
+    }
+
+    fn evaluate(word: &Word, generators: &[FaceletPerm]) -> FaceletPerm {
+        word.iter()
+            .fold(FaceletPerm::identity(), |perm, (i, exponent)| {
+                let order = generators[*i].order() as i64;
+                perm * generators[*i].pow(exponent.rem_euclid(order) as usize)
+            })
+    }
+
+    fn chain(generators: &[&str]) -> (StabilizerChain, Vec<FaceletPerm>) {
+        let generators: Vec<FaceletPerm> = generators.iter().map(|x| perm(x)).collect();
+        (StabilizerChain::new(&generators), generators)
+    }
+
+    #[test]
+    fn test_facelet_perm() {
+        for id in (0..3674160).step_by(99991) {
+            let mut pc = PocketCube::new();
+            pc.apply_id(PermId::from_id(id));
+            assert_eq!(
+                FaceletPerm::from_pocket_cube(&pc).to_facelets(),
+                pc.to_facelets().unwrap()
+            );
+        }
+        let mut pc = PocketCube::new();
+        Algorithm::parse("R U F' R2").unwrap().apply(&mut pc);
+        assert_eq!(perm("R U F' R2").to_facelets(), pc.to_facelets().unwrap());
+        // the opposite turns move the heart piece instead
+        assert_eq!(perm("R L'"), perm("x"));
+        assert_eq!(perm("U D'"), perm("y"));
+        assert_eq!(perm("F B'"), perm("z"));
+        assert_eq!(perm("z").to_facelets()[12..16], SOLVED_FACELETS[0..4]);
+        assert_eq!(perm("R U R' U'").order(), 6);
+    }
+
+    #[test]
+    fn test_order() {
+        assert_eq!(chain(&["R"]).0.order(), 4);
+        assert_eq!(chain(&["R", "U"]).0.order(), 29160);
+        assert_eq!(chain(&["R", "U", "F"]).0.order(), 3674160);
+        // every state in every orientation
+        assert_eq!(chain(&["R", "U", "F", "L", "D", "B"]).0.order(), 88179840);
+        assert_eq!(chain(&["x", "y"]).0.order(), 24);
+        assert_eq!(chain(&[""]).0.order(), 1);
+    }
+
+    #[test]
+    fn test_arbitrary_perm() {
+        let u = perm("U");
+        assert_eq!(FaceletPerm::parse(&u.to_string()), Ok(u));
+        assert_eq!(FaceletPerm::try_from(u.images), Ok(u));
+        assert_eq!(FaceletPerm::parse("()"), Ok(FaceletPerm::identity()));
+        assert_eq!(
+            FaceletPerm::parse("(0, 1)(2 3)"),
+            FaceletPerm::parse("(2 3) (1 0)")
+        );
+        assert_eq!(FaceletPerm::parse("(0 24)"), Err(PermError::Facelet(24)));
+        assert_eq!(
+            FaceletPerm::parse("(0 1) (1 2)"),
+            Err(PermError::Repeated(1))
+        );
+        assert_eq!(FaceletPerm::parse("(0 1"), Err(PermError::Syntax(4)));
+        assert_eq!(FaceletPerm::parse("0 1"), Err(PermError::Syntax(1)));
+        let mut images = FaceletPerm::identity().images;
+        images[0] = 1;
+        assert_eq!(FaceletPerm::try_from(images), Err(PermError::Repeated(1)));
+
+        // a single facelet transposition is not a cube state, and with a
+        // 6-cycle it generates every permutation of those facelets
+        let cycle = FaceletPerm::parse("(0 1 2 3 4 5)").unwrap();
+        let swap = FaceletPerm::parse("(0 1)").unwrap();
+        let chain = StabilizerChain::new(&[cycle, swap]);
+        assert_eq!(chain.order(), 720);
+        assert!(!self::chain(&["R", "U", "F", "L", "D", "B"])
+            .0
+            .contains(&swap));
+    }
+
+    #[test]
+    fn test_factorize() {
+        let (chain, generators) = chain(&["R", "U", "F", "L"]);
+        for (strong, word) in chain.get_strong_generators() {
+            assert_eq!(evaluate(word, &generators), *strong);
+        }
+        for text in ["x", "R U R' U'", "D B2", "[L: U] y2"] {
+            let target = perm(text);
+            let word = chain.factorize(&target).unwrap();
+            assert_eq!(evaluate(&word, &generators), target, "{}", text);
+        }
+        let (chain, _) = self::chain(&["R", "U"]);
+        assert!(chain.contains(&perm("R U R' U'")));
+        assert!(!chain.contains(&perm("F")));
+        assert!(!chain.contains(&perm("x")));
+    }
+}
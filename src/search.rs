@@ -0,0 +1,107 @@
+// Optimal distances over the MiniTable graph
+//
+// Distances are measured in quarter turns (R, U, F and their inverses). The
+// inverse of a move is found by following its MiniTable column three times.
+
+use crate::calc::BranchTable;
+use std::collections::VecDeque;
+
+pub const DISTANCE_UNKNOWN: u8 = u8::MAX;
+
+// Targets of R, U, F, R', U', F'
+pub fn get_neighbors(table: &dyn BranchTable, id: u32) -> [u32; 6] {
+    let row = table.get_branches(id);
+    let mut neighbors = [0; 6];
+    neighbors[..3].copy_from_slice(&row);
+    for (j, inverse) in neighbors[3..].iter_mut().enumerate() {
+        let twice = table.get_branches(row[j])[j];
+        *inverse = table.get_branches(twice)[j];
+    }
+    neighbors
+}
+
+pub fn generate_neighbor_table(table: &dyn BranchTable, state_count: usize) -> Vec<[u32; 6]> {
+    (0..state_count as u32)
+        .map(|id| get_neighbors(table, id))
+        .collect()
+}
+
+// Breadth-first search from the solved state (id 0)
+pub fn compute_distances(table: &dyn BranchTable, state_count: usize) -> Vec<u8> {
+    let mut distances = vec![DISTANCE_UNKNOWN; state_count];
+    let mut queue: VecDeque<u32> = VecDeque::new();
+    distances[0] = 0;
+    queue.push_back(0);
+    while let Some(id) = queue.pop_front() {
+        let distance = distances[id as usize] + 1;
+        for neighbor in get_neighbors(table, id) {
+            if distances[neighbor as usize] == DISTANCE_UNKNOWN {
+                distances[neighbor as usize] = distance;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    distances
+}
+
+// Number of states at every distance
+pub fn distance_histogram(distances: &[u8]) -> Vec<usize> {
+    let mut histogram: Vec<usize> = Vec::new();
+    for distance in distances.iter().filter(|x| **x != DISTANCE_UNKNOWN) {
+        let distance = *distance as usize;
+        if histogram.len() <= distance {
+            histogram.resize(distance + 1, 0);
+        }
+        histogram[distance] += 1;
+    }
+    histogram
+}
+
+// Probability mass at every distance, e.g. of an `MtableIterator` distribution.
+// The mass of states not reached from the solved state is left out.
+pub fn distance_distribution(probs: &[f64], distances: &[u8]) -> Vec<f64> {
+    let mut distribution: Vec<f64> = Vec::new();
+    let known = probs
+        .iter()
+        .zip(distances)
+        .filter(|(_, x)| **x != DISTANCE_UNKNOWN);
+    for (prob, distance) in known {
+        let distance = *distance as usize;
+        if distribution.len() <= distance {
+            distribution.resize(distance + 1, 0.0);
+        }
+        distribution[distance] += prob;
+    }
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::MiniTable;
+
+    #[test]
+    fn test_cycle_distances() {
+        // every move steps forward on a cycle of 4 states
+        let mtable: MiniTable = (0..4).map(|i| [(i + 1) % 4; 4]).collect();
+        assert_eq!(compute_distances(&mtable, 4), vec![0, 1, 2, 1]);
+        assert_eq!(distance_histogram(&[0, 1, 2, 1]), vec![1, 2, 1]);
+        assert_eq!(
+            distance_distribution(&[0.25, 0.25, 0.0, 0.5], &[0, 1, 2, 1]),
+            vec![0.25, 0.75, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_disconnected_distances() {
+        // states 0, 1 and states 2, 3 form two separate cycles of 2
+        let mtable: MiniTable = vec![[1; 4], [0; 4], [3; 4], [2; 4]];
+        let distances = compute_distances(&mtable, 4);
+        assert_eq!(distances, vec![0, 1, DISTANCE_UNKNOWN, DISTANCE_UNKNOWN]);
+        assert_eq!(distance_histogram(&distances), vec![1, 1]);
+        assert_eq!(
+            distance_distribution(&[0.25, 0.25, 0.25, 0.25], &distances),
+            vec![0.25, 0.25]
+        );
+    }
+}
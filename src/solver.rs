@@ -0,0 +1,147 @@
+// Noisy-optimal solver model
+//
+// At every step the solver makes an optimal move (one that reduces the optimal
+// distance, chosen uniformly among them) with probability p, and a random R, U
+// or F move otherwise. The solved state is absorbing.
+
+use crate::error::CubeError;
+
+pub struct NoisySolver<'a> {
+    neighbors: &'a [[u32; 6]],
+    distances: &'a [u8],
+    p: f64,
+    probs: Vec<f64>,
+}
+
+pub struct SolveSummary {
+    pub solved: Vec<f64>,
+    pub expected_length: f64,
+    pub remaining: f64,
+}
+
+impl<'a> NoisySolver<'a> {
+    // `neighbors` comes from `search::generate_neighbor_table`. Every unsolved
+    // state needs a neighbor closer to the solved state, otherwise its share of
+    // optimal moves would be lost, so a state with an unknown distance (from a
+    // damaged or partial table) is an error.
+    pub fn new(neighbors: &'a [[u32; 6]], distances: &'a [u8], p: f64) -> Result<Self, CubeError> {
+        for (id, (row, distance)) in neighbors.iter().zip(distances).enumerate().skip(1) {
+            if row.iter().all(|x| distances[*x as usize] >= *distance) {
+                return Err(CubeError::NoOptimalMove(id as u32));
+            }
+        }
+        Ok(Self {
+            neighbors,
+            distances,
+            p,
+            probs: vec![0.0; neighbors.len()],
+        })
+    }
+
+    // Uniformly random starting state
+    pub fn set_uniform(&mut self) {
+        let prob = 1.0 / (self.probs.len() as f64);
+        self.probs.iter_mut().for_each(|x| *x = prob);
+    }
+
+    pub fn set_state(&mut self, id: u32) {
+        self.probs.iter_mut().for_each(|x| *x = 0.0);
+        self.probs[id as usize] = 1.0;
+    }
+
+    pub fn iterate(&mut self) {
+        let mut new_probs: Vec<f64> = vec![0.0; self.probs.len()];
+        new_probs[0] = self.probs[0];
+        for i in 1..self.probs.len() {
+            let prob = self.probs[i];
+            if prob == 0.0 {
+                continue;
+            }
+
+            let neighbors = &self.neighbors[i];
+            let distance = self.distances[i];
+            let optimal_count = neighbors
+                .iter()
+                .filter(|x| self.distances[**x as usize] < distance)
+                .count();
+            for neighbor in neighbors {
+                if self.distances[*neighbor as usize] < distance {
+                    new_probs[*neighbor as usize] += prob * self.p / (optimal_count as f64);
+                }
+            }
+            for neighbor in &neighbors[..3] {
+                new_probs[*neighbor as usize] += prob * (1.0 - self.p) / 3.0;
+            }
+        }
+        self.probs = new_probs;
+    }
+
+    pub fn get_solved(&self) -> f64 {
+        self.probs[0]
+    }
+
+    // Probability of being solved by each step, and the expected solve length
+    // truncated to the given number of steps, i.e. E[min(T, steps)]
+    pub fn run(&mut self, steps: usize) -> SolveSummary {
+        let mut solved = Vec::new();
+        // P(T > n) summed over n gives E[T]
+        let mut expected_length = 1.0 - self.get_solved();
+        for _ in 0..steps {
+            self.iterate();
+            solved.push(self.get_solved());
+            expected_length += 1.0 - self.get_solved();
+        }
+        let remaining = 1.0 - self.get_solved();
+        // the last term is P(T > steps), which lies past the truncation
+        expected_length -= remaining;
+        SolveSummary {
+            solved,
+            expected_length,
+            remaining,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::MiniTable;
+    use crate::search::{compute_distances, generate_neighbor_table};
+
+    #[test]
+    fn test_optimal_solver_on_cycle() {
+        let mtable: MiniTable = (0..4).map(|i| [(i + 1) % 4; 4]).collect();
+        let distances = compute_distances(&mtable, 4);
+        let neighbors = generate_neighbor_table(&mtable, 4);
+        let mut solver = NoisySolver::new(&neighbors, &distances, 1.0).unwrap();
+        solver.set_state(2);
+        let summary = solver.run(3);
+        assert_eq!(summary.solved[0], 0.0);
+        assert!((summary.expected_length - 2.0).abs() < 1e-12);
+        assert!(summary.remaining.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_random_solver_on_cycle() {
+        let mtable: MiniTable = (0..4).map(|i| [(i + 1) % 4; 4]).collect();
+        let distances = compute_distances(&mtable, 4);
+        let neighbors = generate_neighbor_table(&mtable, 4);
+        let mut solver = NoisySolver::new(&neighbors, &distances, 0.0).unwrap();
+        solver.set_state(1);
+        let summary = solver.run(4);
+        assert_eq!(summary.solved, vec![0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(summary.expected_length, 3.0);
+    }
+
+    #[test]
+    fn test_unreachable_state() {
+        // state 3 only leads to itself
+        let mtable: MiniTable = vec![[1; 4], [0; 4], [0; 4], [3; 4]];
+        let distances = compute_distances(&mtable, 4);
+        let neighbors = generate_neighbor_table(&mtable, 4);
+        assert!(matches!(
+            NoisySolver::new(&neighbors, &distances, 0.5),
+            Err(CubeError::NoOptimalMove(3))
+        ));
+    }
+}
@@ -0,0 +1,278 @@
+// Cube states as group elements
+//
+// A `CubeState` is the state of the 7 non-heart cubics, like `PocketCube`, but
+// as a plain value: `positions[i]` is the `CornerPos` of the cubic that starts
+// at position `i`, and `rotations[i]` its `CornerTwist`. Every state is also the
+// permutation that takes the solved cube to it, so states can be composed:
+//
+//     a * b    a followed by b, as in the move sequence `A B`
+//
+// A move only adds a twist that depends on the position it moves a cubic from,
+// so a cubic at position p in `a` moves to `b.positions[p]` and gains the twist
+// `b.rotations[p]`.
+//
+// A state is displayed in cycle notation over the positions, e.g.
+// `(FUR BUR FUL)+ (FDR)-`: the cubic at FUR moves to BUR and so on, and a cycle
+// is followed by `+` or `-` if the twists of its cubics add up to 1 or 2. Fixed
+// cubics without a twist are left out, and the solved state is `()`.
+
+use crate::enums::{CornerPos, CORNER_POSITIONS, CORNER_TWISTS};
+use crate::group::PocketCube;
+use crate::moves::Move;
+use crate::types::PermId;
+use std::fmt;
+use std::ops::Mul;
+
+// Ordered by `positions`, then `rotations`, which is cheap but not the order of
+// the `PermId`s. The solved state still comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CubeState {
+    positions: [u8; 7],
+    rotations: [u8; 7],
+}
+
+// A cycle of positions, starting at the lowest one
+#[derive(Debug, Clone)]
+pub struct Cycle {
+    pub positions: Vec<CornerPos>,
+    // sum of the twists of the cubics in the cycle, mod 3
+    pub twist: u8,
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl CubeState {
+    pub fn identity() -> Self {
+        Self {
+            positions: [0, 1, 2, 3, 4, 5, 6],
+            rotations: [0; 7],
+        }
+    }
+
+    pub fn from_pocket_cube(pc: &PocketCube) -> Self {
+        let cubics = pc.get_cubics();
+        Self {
+            positions: cubics.map(|x| u32::from(x.get_pos()) as u8),
+            rotations: cubics.map(|x| u32::from(x.get_rot()) as u8),
+        }
+    }
+
+    pub fn to_pocket_cube(self) -> PocketCube {
+        let mut pc = PocketCube::new();
+        for i in 0..7 {
+            pc.cubics[i].set_pos(CORNER_POSITIONS[self.positions[i] as usize]);
+            pc.cubics[i].set_rot(CORNER_TWISTS[self.rotations[i] as usize]);
+        }
+        pc
+    }
+
+    pub fn get_perm_id(&self) -> PermId {
+        self.to_pocket_cube().get_perm_id()
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut inverse = Self::identity();
+        for i in 0..7 {
+            let pos = self.positions[i] as usize;
+            inverse.positions[pos] = i as u8;
+            inverse.rotations[pos] = (3 - self.rotations[i]) % 3;
+        }
+        inverse
+    }
+
+    // The state that takes `self` to `other`, `self * x = other`
+    pub fn to(&self, other: &CubeState) -> Self {
+        self.inverse() * *other
+    }
+
+    // Every cycle, including the fixed cubics
+    pub fn get_cycles(&self) -> Vec<Cycle> {
+        let mut visited = [false; 7];
+        let mut cycles = Vec::new();
+        for start in 0..7 {
+            let mut cycle = Cycle {
+                positions: Vec::new(),
+                twist: 0,
+            };
+            let mut i = start;
+            while !visited[i] {
+                visited[i] = true;
+                cycle.positions.push(CORNER_POSITIONS[i]);
+                cycle.twist = (cycle.twist + self.rotations[i]) % 3;
+                i = self.positions[i] as usize;
+            }
+            if !cycle.positions.is_empty() {
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    // Lengths of the cycles in descending order, adding up to 7
+    pub fn get_cycle_type(&self) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self
+            .get_cycles()
+            .iter()
+            .map(|x| x.positions.len())
+            .collect();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths
+    }
+
+    // Parity of the permutation of the positions, a cycle of length n is n - 1
+    // transpositions
+    pub fn is_even(&self) -> bool {
+        self.get_cycle_type().iter().map(|x| x - 1).sum::<usize>() % 2 == 0
+    }
+
+    // Smallest n > 0 with self^n = identity: every cycle of cubics returns after
+    // its length, or after three times its length if it adds a twist
+    pub fn order(&self) -> usize {
+        let mut order = 1;
+        for cycle in self.get_cycles() {
+            let mut length = cycle.positions.len();
+            if cycle.twist != 0 {
+                length *= 3;
+            }
+            order = order / gcd(order, length) * length;
+        }
+        order
+    }
+}
+
+impl Mul for CubeState {
+    type Output = CubeState;
+
+    fn mul(self, rhs: CubeState) -> CubeState {
+        let mut product = self;
+        for i in 0..7 {
+            let pos = self.positions[i] as usize;
+            product.positions[i] = rhs.positions[pos];
+            product.rotations[i] = (self.rotations[i] + rhs.rotations[pos]) % 3;
+        }
+        product
+    }
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let positions: Vec<String> = self.positions.iter().map(|x| format!("{:?}", x)).collect();
+        let twist = ["", "+", "-"][self.twist as usize];
+        write!(f, "({}){}", positions.join(" "), twist)
+    }
+}
+
+impl fmt::Display for CubeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cycles: Vec<String> = self
+            .get_cycles()
+            .iter()
+            .filter(|x| x.positions.len() > 1 || x.twist != 0)
+            .map(|x| x.to_string())
+            .collect();
+        if cycles.is_empty() {
+            write!(f, "()")
+        } else {
+            write!(f, "{}", cycles.join(" "))
+        }
+    }
+}
+
+impl From<PermId> for CubeState {
+    fn from(id: PermId) -> Self {
+        let mut pc = PocketCube::new();
+        pc.apply_id(id);
+        Self::from_pocket_cube(&pc)
+    }
+}
+
+impl From<CubeState> for PermId {
+    fn from(state: CubeState) -> Self {
+        state.get_perm_id()
+    }
+}
+
+impl From<Move> for CubeState {
+    fn from(mv: Move) -> Self {
+        let mut pc = PocketCube::new();
+        pc.apply(mv);
+        Self::from_pocket_cube(&pc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::Algorithm;
+    use crate::moves::MOVES;
+
+    fn state(text: &str) -> CubeState {
+        CubeState::from(Algorithm::parse(text).unwrap().get_perm_id())
+    }
+
+    #[test]
+    fn test_mul_matches_moves() {
+        let mut product = CubeState::identity();
+        let mut pc = PocketCube::new();
+        for mv in [Move::R, Move::U2, Move::FPrime, Move::R, Move::F] {
+            product = product * CubeState::from(mv);
+            pc.apply(mv);
+            assert_eq!(product, CubeState::from_pocket_cube(&pc));
+        }
+        assert_eq!(state("R U") * state("F' R2"), state("R U F' R2"));
+    }
+
+    #[test]
+    fn test_group_axioms() {
+        for id in (0..3674160).step_by(104729) {
+            let a = CubeState::from(PermId::from_id(id));
+            assert_eq!(PermId::from(a).get_id(), id);
+            assert_eq!(a * a.inverse(), CubeState::identity());
+            assert_eq!(a.inverse() * a, CubeState::identity());
+            let b = state("R U' F2");
+            assert_eq!(a.to(&b), a.inverse() * b);
+            assert_eq!(a * a.to(&b), b);
+            assert_eq!((a * b) * a, a * (b * a));
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        assert_eq!(CubeState::identity().order(), 1);
+        for mv in MOVES {
+            let expected = if mv.quarter_turns() == 2 { 2 } else { 4 };
+            assert_eq!(CubeState::from(mv).order(), expected, "{}", mv);
+        }
+        assert_eq!(state("R U R' U'").order(), 6);
+        let a = state("R U F' R2 U");
+        let mut power = CubeState::identity();
+        for _ in 0..a.order() - 1 {
+            power = power * a;
+            assert_ne!(power, CubeState::identity());
+        }
+        assert_eq!(power * a, CubeState::identity());
+        assert!(CubeState::identity() < state("R"));
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(CubeState::identity().to_string(), "()");
+        assert_eq!(CubeState::from(Move::U).to_string(), "(FUR FUL BUL BUR)");
+        assert_eq!(CubeState::from(Move::R2).to_string(), "(FUR BDR) (FDR BUR)");
+        assert_eq!(CubeState::from(Move::U).get_cycle_type(), vec![4, 1, 1, 1]);
+        assert!(!CubeState::from(Move::U).is_even());
+        assert!(CubeState::from(Move::R2).is_even());
+        // the sexy move swaps two pairs and twists two more cubics
+        let sexy = state("R U R' U'");
+        assert_eq!(sexy.get_cycle_type(), vec![2, 2, 1, 1, 1]);
+        let twisted: Vec<u8> = sexy.get_cycles().iter().map(|x| x.twist).collect();
+        assert_eq!(twisted.iter().filter(|x| **x != 0).count(), 2);
+        assert_eq!(sexy.order(), 6);
+    }
+}
@@ -0,0 +1,146 @@
+// Subgroups generated by move sets or arbitrary states
+//
+// The subgroup is enumerated by a breadth-first search from the solved state,
+// applying every generator and its inverse, so the depth of a state is the
+// length of its shortest word in the generators and their inverses (for moves
+// like R and U, its distance in quarter turns).
+//
+// The states are re-indexed compactly in `PermId` order, so index 0 is still
+// the solved state. With at most 3 generators a MiniTable over the indexes
+// drives an `MtableIterator`, with a column per generator.
+
+use crate::calc::{MiniTable, MtableIterator};
+use crate::search::DISTANCE_UNKNOWN;
+use crate::state::CubeState;
+use crate::types::{PermId, PERMID_COUNT};
+use std::collections::VecDeque;
+
+pub struct Subgroup {
+    generators: Vec<CubeState>,
+    // `PermId` of every index, ascending
+    ids: Vec<u32>,
+    // depth of every index
+    depths: Vec<u8>,
+}
+
+impl Subgroup {
+    pub fn generate(generators: Vec<CubeState>) -> Self {
+        let steps: Vec<CubeState> = generators.iter().flat_map(|x| [*x, x.inverse()]).collect();
+        let mut depths = vec![DISTANCE_UNKNOWN; PERMID_COUNT];
+        let mut queue = VecDeque::from([CubeState::identity()]);
+        depths[0] = 0;
+        while let Some(state) = queue.pop_front() {
+            let depth = depths[state.get_perm_id().get_id() as usize] + 1;
+            for step in &steps {
+                let next = state * *step;
+                let id = next.get_perm_id().get_id() as usize;
+                if depths[id] == DISTANCE_UNKNOWN {
+                    depths[id] = depth;
+                    queue.push_back(next);
+                }
+            }
+        }
+        let ids: Vec<u32> = (0..PERMID_COUNT as u32)
+            .filter(|x| depths[*x as usize] != DISTANCE_UNKNOWN)
+            .collect();
+        let depths = ids.iter().map(|x| depths[*x as usize]).collect();
+        Self {
+            generators,
+            ids,
+            depths,
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn get_depths(&self) -> &[u8] {
+        &self.depths
+    }
+
+    pub fn get_perm_id(&self, index: u32) -> PermId {
+        PermId::from_id(self.ids[index as usize])
+    }
+
+    // `None` if the state is not in the subgroup
+    pub fn get_index(&self, id: PermId) -> Option<u32> {
+        self.ids.binary_search(&id.get_id()).ok().map(|x| x as u32)
+    }
+
+    // Columns are the generators in order, unused columns stay in place. `None`
+    // with more than 3 generators.
+    pub fn generate_table(&self) -> Option<MiniTable> {
+        if self.generators.len() > 3 {
+            return None;
+        }
+        let table = (0..self.order() as u32)
+            .map(|index| {
+                let state = CubeState::from(self.get_perm_id(index));
+                let mut row = [index, index, index, 1];
+                for (j, generator) in self.generators.iter().enumerate() {
+                    let id = (state * *generator).get_perm_id();
+                    row[j] = self.get_index(id).unwrap();
+                }
+                row
+            })
+            .collect();
+        Some(table)
+    }
+
+    // Walk choosing every generator with the same probability, starting at the
+    // solved state
+    pub fn create_iterator(&self) -> Option<MtableIterator> {
+        let table = self.generate_table()?;
+        let mut iterator = MtableIterator::with_state_count(self.order());
+        let mut weights = [0.0; 3];
+        weights[..self.generators.len()].fill(1.0 / self.generators.len() as f64);
+        iterator.load_mtable(table);
+        iterator.set_weights(weights);
+        iterator.set_state(0);
+        Some(iterator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::search::distance_histogram;
+
+    #[test]
+    fn test_single_move() {
+        let subgroup = Subgroup::generate(vec![CubeState::from(Move::R)]);
+        assert_eq!(subgroup.order(), 4);
+        assert_eq!(distance_histogram(subgroup.get_depths()), vec![1, 2, 1]);
+        let r = CubeState::from(Move::R).get_perm_id();
+        assert_eq!(
+            subgroup
+                .get_perm_id(subgroup.get_index(r).unwrap())
+                .get_id(),
+            r.get_id()
+        );
+        assert!(subgroup
+            .get_index(CubeState::from(Move::U).get_perm_id())
+            .is_none());
+    }
+
+    #[test]
+    fn test_iterator() {
+        let subgroup = Subgroup::generate(vec![CubeState::from(Move::R), CubeState::from(Move::U)]);
+        assert_eq!(subgroup.order(), 29160);
+        let table = subgroup.generate_table().unwrap();
+        assert!((0..table.len()).all(|i| table[i][2] == i as u32));
+
+        // only R R R R and U U U U return after 4 steps
+        let mut iterator = subgroup.create_iterator().unwrap();
+        iterator.set_absorbing(&[]);
+        for _ in 0..4 {
+            iterator.iterate(false);
+        }
+        assert!((iterator.get_prob(0).unwrap() - 0.125).abs() < 1e-12);
+        assert!(Subgroup::generate(vec![CubeState::identity(); 4])
+            .create_iterator()
+            .is_none());
+    }
+}
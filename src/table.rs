@@ -0,0 +1,309 @@
+// Header of the binary table files
+//
+// Every table file starts with a 64 byte header, followed by the rows. All
+// header fields are little-endian; the endianness flag describes the rows.
+//
+//     offset  size  field
+//          0     8  magic "PCPTABLE"
+//          8     4  format version
+//         12     4  endianness of the rows (0 = little, 1 = big)
+//         16    16  move set of the columns, e.g. "R U F" (NUL padded)
+//         32     4  row width in bytes
+//         36     4  state count (number of rows)
+//         40     8  FNV-1a checksum of the rows
+//         48    16  reserved (zero)
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+
+pub const TABLE_MAGIC: [u8; 8] = *b"PCPTABLE";
+pub const TABLE_VERSION: u32 = 1;
+pub const TABLE_HEADER_SIZE: usize = 64;
+
+const MOVES_SIZE: usize = 16;
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableHeader {
+    pub version: u32,
+    pub endianness: Endianness,
+    pub moves: String,
+    pub row_width: u32,
+    pub state_count: u32,
+    pub checksum: u64,
+}
+
+#[derive(Debug)]
+pub enum TableError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    BadEndianness(u32),
+    MoveSetMismatch { expected: String, found: String },
+    RowWidthMismatch { expected: u32, found: u32 },
+    StateCountMismatch { expected: u32, found: u32 },
+    Truncated { expected: u64, found: u64 },
+    ChecksumMismatch { expected: u64, found: u64 },
+}
+
+// Streaming FNV-1a hash of the row bytes
+#[derive(Clone, Copy)]
+pub struct Checksum {
+    hash: u64,
+}
+
+impl Checksum {
+    pub fn new() -> Self {
+        Self { hash: FNV_OFFSET }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn get(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl TableHeader {
+    pub fn new(moves: &str, row_width: u32, state_count: u32, checksum: u64) -> Self {
+        Self {
+            version: TABLE_VERSION,
+            endianness: Endianness::Little,
+            moves: moves.to_string(),
+            row_width,
+            state_count,
+            checksum,
+        }
+    }
+
+    // Size of the rows following the header
+    pub fn data_size(&self) -> u64 {
+        self.row_width as u64 * self.state_count as u64
+    }
+
+    pub fn to_bytes(&self) -> [u8; TABLE_HEADER_SIZE] {
+        let mut bytes = [0; TABLE_HEADER_SIZE];
+        bytes[0..8].copy_from_slice(&TABLE_MAGIC);
+        bytes[8..12].copy_from_slice(&self.version.to_le_bytes());
+        let endianness: u32 = match self.endianness {
+            Endianness::Little => 0,
+            Endianness::Big => 1,
+        };
+        bytes[12..16].copy_from_slice(&endianness.to_le_bytes());
+        let moves = self.moves.as_bytes();
+        let moves_len = moves.len().min(MOVES_SIZE);
+        bytes[16..16 + moves_len].copy_from_slice(&moves[..moves_len]);
+        bytes[32..36].copy_from_slice(&self.row_width.to_le_bytes());
+        bytes[36..40].copy_from_slice(&self.state_count.to_le_bytes());
+        bytes[40..48].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; TABLE_HEADER_SIZE]) -> Result<Self, TableError> {
+        if bytes[0..8] != TABLE_MAGIC {
+            return Err(TableError::BadMagic);
+        }
+        let read_u32 = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let version = read_u32(8);
+        if version != TABLE_VERSION {
+            return Err(TableError::UnsupportedVersion(version));
+        }
+        let endianness = match read_u32(12) {
+            0 => Endianness::Little,
+            1 => Endianness::Big,
+            x => return Err(TableError::BadEndianness(x)),
+        };
+        let moves: String = bytes[16..32]
+            .iter()
+            .take_while(|x| **x != 0)
+            .map(|x| *x as char)
+            .collect();
+
+        Ok(Self {
+            version,
+            endianness,
+            moves,
+            row_width: read_u32(32),
+            state_count: read_u32(36),
+            checksum: u64::from_le_bytes(bytes[40..48].try_into().unwrap()),
+        })
+    }
+
+    pub fn read(reader: &mut impl Read) -> io::Result<[u8; TABLE_HEADER_SIZE]> {
+        let mut bytes = [0; TABLE_HEADER_SIZE];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    // Checks that the table was built for the expected layout
+    pub fn validate(
+        &self,
+        moves: &str,
+        row_width: u32,
+        state_count: u32,
+    ) -> Result<(), TableError> {
+        if self.moves != moves {
+            return Err(TableError::MoveSetMismatch {
+                expected: moves.to_string(),
+                found: self.moves.clone(),
+            });
+        }
+        if self.row_width != row_width {
+            return Err(TableError::RowWidthMismatch {
+                expected: row_width,
+                found: self.row_width,
+            });
+        }
+        if self.state_count != state_count {
+            return Err(TableError::StateCountMismatch {
+                expected: state_count,
+                found: self.state_count,
+            });
+        }
+        Ok(())
+    }
+
+    // `size` is the number of bytes following the header
+    pub fn validate_size(&self, size: u64) -> Result<(), TableError> {
+        if size != self.data_size() {
+            return Err(TableError::Truncated {
+                expected: self.data_size(),
+                found: size,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn validate_checksum(&self, checksum: &Checksum) -> Result<(), TableError> {
+        if checksum.get() != self.checksum {
+            return Err(TableError::ChecksumMismatch {
+                expected: self.checksum,
+                found: checksum.get(),
+            });
+        }
+        Ok(())
+    }
+}
+
+// Opens a table file and reads its header, checking that the file size matches
+// it. The reader is positioned at the first row.
+pub fn open_table_header(fname: &str) -> Result<(BufReader<File>, TableHeader), TableError> {
+    let f = File::open(fname)?;
+    let size = f.metadata()?.len();
+    let mut reader = BufReader::new(f);
+    let header_bytes = TableHeader::read(&mut reader).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => TableError::Truncated {
+            expected: TABLE_HEADER_SIZE as u64,
+            found: size,
+        },
+        _ => TableError::Io(e),
+    })?;
+    let header = TableHeader::from_bytes(&header_bytes)?;
+    header.validate_size(size - TABLE_HEADER_SIZE as u64)?;
+    Ok((reader, header))
+}
+
+// Opens a table file and validates its header against the expected layout
+pub fn open_table(
+    fname: &str,
+    moves: &str,
+    row_width: u32,
+    state_count: u32,
+) -> Result<(BufReader<File>, TableHeader), TableError> {
+    let (reader, header) = open_table_header(fname)?;
+    header.validate(moves, row_width, state_count)?;
+    Ok((reader, header))
+}
+
+impl From<io::Error> for TableError {
+    fn from(e: io::Error) -> Self {
+        TableError::Io(e)
+    }
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Io(e) => write!(f, "{}", e),
+            TableError::BadMagic => write!(
+                f,
+                "Not a table file, or a table from before the versioned format (regenerate it with `gen`)"
+            ),
+            TableError::UnsupportedVersion(x) => write!(
+                f,
+                "Unsupported table format version {} (expected {}), regenerate it with `gen`",
+                x, TABLE_VERSION
+            ),
+            TableError::BadEndianness(x) => write!(f, "Invalid endianness flag {}", x),
+            TableError::MoveSetMismatch { expected, found } => write!(
+                f,
+                "Table was built for the moves '{}' but '{}' were expected",
+                found, expected
+            ),
+            TableError::RowWidthMismatch { expected, found } => write!(
+                f,
+                "Table rows are {} bytes wide but {} were expected",
+                found, expected
+            ),
+            TableError::StateCountMismatch { expected, found } => write!(
+                f,
+                "Table has {} states but {} were expected",
+                found, expected
+            ),
+            TableError::Truncated { expected, found } => write!(
+                f,
+                "Table file is truncated or incomplete: expected {} bytes of data but found {}",
+                expected, found
+            ),
+            TableError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Table checksum mismatch (header {:#018x}, data {:#018x}), the file is corrupted",
+                expected, found
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = TableHeader::new("R U F", 16, 3674160, 0xdeadbeef);
+        let parsed = TableHeader::from_bytes(&header.to_bytes()).unwrap();
+        assert_eq!(parsed, header);
+        assert!(parsed.validate("R U F", 16, 3674160).is_ok());
+        assert!(matches!(
+            parsed.validate("R U", 16, 3674160),
+            Err(TableError::MoveSetMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_header_bad_magic() {
+        let mut bytes = TableHeader::new("R U F", 16, 1, 0).to_bytes();
+        bytes[0] = b'X';
+        assert!(matches!(
+            TableHeader::from_bytes(&bytes),
+            Err(TableError::BadMagic)
+        ));
+    }
+}
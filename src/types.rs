@@ -1,3 +1,5 @@
+use crate::error::CubeError;
+
 pub const PERMID_POSCOUNT: u32 = 5040; // 7!
 pub const PERMID_ROTCOUNT: u32 = 729; // 3^6
 pub const PERMID_COUNT: usize = (PERMID_POSCOUNT * PERMID_ROTCOUNT) as usize;
@@ -20,8 +22,9 @@ pub struct PermId {
 // ================ IDs ================
 
 impl RotId {
+    // `rid` must be below `PERMID_ROTCOUNT`, use `try_from` for untrusted ids
     pub fn new(rid: u32) -> Self {
-        // todo: add min/max checks
+        debug_assert!(rid < PERMID_ROTCOUNT);
         Self { rid }
     }
     pub fn get_id(&self) -> u32 {
@@ -30,8 +33,9 @@ impl RotId {
 }
 
 impl PosId {
+    // `pid` must be below `PERMID_POSCOUNT`, use `try_from` for untrusted ids
     pub fn new(pid: u32) -> Self {
-        // todo: add min/max checks
+        debug_assert!(pid < PERMID_POSCOUNT);
         Self { pid }
     }
     pub fn get_id(&self) -> u32 {
@@ -49,6 +53,17 @@ impl PermId {
         }
     }
 
+    // `id` must be below `PERMID_COUNT`, e.g. a state enumerated by the crate,
+    // use `try_from` for untrusted ids
+    pub fn from_id(id: u32) -> Self {
+        debug_assert!((id as usize) < PERMID_COUNT);
+        Self {
+            id,
+            pid: PosId::new(id % PERMID_POSCOUNT),
+            rid: RotId::new(id / PERMID_POSCOUNT),
+        }
+    }
+
     pub fn get_rot_id(&self) -> &RotId {
         &self.rid
     }
@@ -60,15 +75,62 @@ impl PermId {
     }
 }
 
-impl From<u32> for PermId {
-    fn from(id: u32) -> Self {
-        let p_id = id % PERMID_POSCOUNT;
-        let r_id = id / PERMID_POSCOUNT;
+impl TryFrom<u32> for RotId {
+    type Error = CubeError;
 
-        Self {
-            id,
-            pid: PosId::new(p_id),
-            rid: RotId::new(r_id),
+    fn try_from(rid: u32) -> Result<Self, CubeError> {
+        if rid >= PERMID_ROTCOUNT {
+            return Err(CubeError::RotId(rid));
+        }
+        Ok(Self::new(rid))
+    }
+}
+
+impl TryFrom<u32> for PosId {
+    type Error = CubeError;
+
+    fn try_from(pid: u32) -> Result<Self, CubeError> {
+        if pid >= PERMID_POSCOUNT {
+            return Err(CubeError::PosId(pid));
         }
+        Ok(Self::new(pid))
+    }
+}
+
+impl TryFrom<u32> for PermId {
+    type Error = CubeError;
+
+    fn try_from(id: u32) -> Result<Self, CubeError> {
+        if id as usize >= PERMID_COUNT {
+            return Err(CubeError::PermId(id));
+        }
+        Ok(Self::from_id(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{CornerPos, CornerTwist};
+
+    #[test]
+    fn test_try_from() {
+        let id = PermId::try_from(5041).unwrap();
+        assert_eq!((id.get_pos_id().get_id(), id.get_rot_id().get_id()), (1, 1));
+        assert!(matches!(
+            PermId::try_from(PERMID_COUNT as u32),
+            Err(CubeError::PermId(3674160))
+        ));
+        assert!(PosId::try_from(PERMID_POSCOUNT).is_err());
+        assert!(RotId::try_from(PERMID_ROTCOUNT - 1).is_ok());
+        assert!(matches!(CornerPos::try_from(7), Ok(CornerPos::BDL)));
+        assert!(matches!(
+            CornerPos::try_from(8),
+            Err(CubeError::CornerPos(8))
+        ));
+        assert!(matches!(
+            CornerTwist::try_from(3),
+            Err(CubeError::CornerTwist(3))
+        ));
     }
 }
@@ -0,0 +1,164 @@
+// Table verification
+//
+// Checks the invariants of a MiniTable: every target is a valid `PermId`, every
+// move has order 4, the targets agree with `PocketCube::get_branches` and the
+// move graph is connected. A corrupted table otherwise silently yields wrong
+// probabilities.
+
+use crate::calc::BranchTable;
+use crate::group::PocketCube;
+use crate::types::PermId;
+
+// Offending rows kept per check
+const MAX_EXAMPLES: usize = 10;
+
+pub struct CheckReport {
+    pub name: &'static str,
+    pub checked: usize,
+    pub failed: usize,
+    // first offending rows, with a description
+    pub examples: Vec<(u32, String)>,
+}
+
+impl CheckReport {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            checked: 0,
+            failed: 0,
+            examples: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, ok: bool, id: u32, message: impl FnOnce() -> String) {
+        self.checked += 1;
+        if !ok {
+            self.failed += 1;
+            if self.examples.len() < MAX_EXAMPLES {
+                self.examples.push((id, message()));
+            }
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+// Every target must be a state of the table
+pub fn check_targets(table: &dyn BranchTable, state_count: usize) -> CheckReport {
+    let mut report = CheckReport::new("targets in range");
+    for id in 0..state_count as u32 {
+        let branches = table.get_branches(id);
+        report.record(
+            branches.iter().all(|x| (*x as usize) < state_count),
+            id,
+            || format!("targets {:?} out of range", branches),
+        );
+    }
+    report
+}
+
+// Applying any move four times returns to the start
+pub fn check_move_order(table: &dyn BranchTable, state_count: usize) -> CheckReport {
+    let mut report = CheckReport::new("moves have order 4");
+    for id in 0..state_count as u32 {
+        for j in 0..3 {
+            let mut target = id;
+            for _ in 0..4 {
+                if target as usize >= state_count {
+                    break;
+                }
+                target = table.get_branches(target)[j];
+            }
+            report.record(target == id, id, || {
+                format!("move {} applied 4 times leads to {}", j, target)
+            });
+        }
+    }
+    report
+}
+
+// Recomputes the targets of the given states from the cube model
+pub fn check_moves(table: &dyn BranchTable, ids: impl Iterator<Item = u32>) -> CheckReport {
+    let mut report = CheckReport::new("targets match the cube model");
+    let mut pc = PocketCube::new();
+    for id in ids {
+        pc.apply_id(PermId::from_id(id));
+        let expected = pc.get_branches().map(|x| x.get_id());
+        let found = table.get_branches(id);
+        report.record(found == expected, id, || {
+            format!("targets {:?}, expected {:?}", found, expected)
+        });
+    }
+    report
+}
+
+// Every state is reachable from the solved state. Since the moves have order 4
+// the forward moves are enough.
+pub fn check_connected(table: &dyn BranchTable, state_count: usize) -> CheckReport {
+    let mut report = CheckReport::new("graph is connected");
+    let mut visited = vec![false; state_count];
+    let mut stack: Vec<u32> = vec![0];
+    visited[0] = true;
+    while let Some(id) = stack.pop() {
+        for target in table.get_branches(id) {
+            if (target as usize) < state_count && !visited[target as usize] {
+                visited[target as usize] = true;
+                stack.push(target);
+            }
+        }
+    }
+    for (id, reached) in visited.iter().enumerate() {
+        report.record(*reached, id as u32, || {
+            "not reachable from the solved state".to_string()
+        });
+    }
+    report
+}
+
+// Runs all checks, comparing every `stride`-th state against the cube model
+pub fn verify_table(
+    table: &dyn BranchTable,
+    state_count: usize,
+    stride: usize,
+) -> Vec<CheckReport> {
+    vec![
+        check_targets(table, state_count),
+        check_move_order(table, state_count),
+        check_moves(table, (0..state_count as u32).step_by(stride)),
+        check_connected(table, state_count),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::MiniTable;
+    use crate::factored::FactoredTable;
+
+    #[test]
+    fn test_cycle_table() {
+        let mut mtable: MiniTable = (0..4).map(|i| [(i + 1) % 4; 4]).collect();
+        assert!(check_targets(&mtable, 4).passed());
+        assert!(check_move_order(&mtable, 4).passed());
+        assert!(check_connected(&mtable, 4).passed());
+
+        mtable[2][1] = 7;
+        let report = check_targets(&mtable, 4);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.examples[0].0, 2);
+        assert!(!check_move_order(&mtable, 4).passed());
+
+        let mtable: MiniTable = vec![[0; 4], [1; 4]];
+        let report = check_connected(&mtable, 2);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.examples[0].0, 1);
+    }
+
+    #[test]
+    fn test_check_moves() {
+        let table = FactoredTable::new();
+        assert!(check_moves(&table, (0..3674160).step_by(9973)).passed());
+    }
+}